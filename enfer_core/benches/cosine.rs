@@ -0,0 +1,43 @@
+//! Times cosine similarities between 768-dimensional embeddings, the size of BERT-base models, to compare the scalar
+//! kernels with the `simd` ones:
+//!
+//! ```text
+//! cargo bench -p enfer_core --bench cosine
+//! cargo +nightly bench -p enfer_core --bench cosine --features simd
+//! ```
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use inference_core::Embedding;
+
+const DIM: usize = 768;
+const CANDIDATES: usize = 10_000;
+const RUNS: u32 = 20;
+
+/// Deterministic values in `[-0.5, 0.5)` from a fixed-seed LCG, so every run compares the same vectors.
+fn embeddings(count: usize) -> Vec<Embedding> {
+    let mut state = 42u64;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as f32 / (1u64 << 31) as f32 - 0.5
+    };
+
+    (0..count).map(|_| Embedding((0..DIM).map(|_| next()).collect())).collect()
+}
+
+fn main() {
+    let query = embeddings(1).remove(0);
+    let candidates = embeddings(CANDIDATES);
+
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        for candidate in &candidates {
+            black_box(black_box(&query).cosine_similarity(candidate).unwrap());
+        }
+    }
+    let elapsed = started.elapsed() / RUNS;
+
+    let kernels = if cfg!(feature = "simd") { "simd" } else { "scalar" };
+    println!("{} kernels: {:?} per {} cosines of {} dimensions", kernels, elapsed, CANDIDATES, DIM);
+}
@@ -0,0 +1,75 @@
+//! Counts the heap allocations and bytes of a single `embed` once the input buffers are pooled, next to a one-sequence
+//! `embed_batch`, whose padded inputs are still allocated on every call. Needs the model in `../model`:
+//!
+//! ```text
+//! cargo bench -p enfer_core --bench embed_allocations
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use inference_core::Semantic;
+
+const RUNS: usize = 1_000;
+const TEXT: &str = "The quick brown fox jumps over the lazy dog, then naps under the old oak tree.";
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs `f` `RUNS` times and returns the allocations, bytes and time of an average call.
+fn measure(mut f: impl FnMut()) -> (f64, f64, Duration) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let started = Instant::now();
+
+    for _ in 0..RUNS {
+        f();
+    }
+
+    let elapsed = started.elapsed() / RUNS as u32;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / RUNS as f64;
+    let bytes = (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) as f64 / RUNS as f64;
+
+    (allocations, bytes, elapsed)
+}
+
+fn main() {
+    let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json")
+        .expect("the benchmark needs the model in ../model");
+    // the first call fills the buffer pool
+    semantic.embed(TEXT).unwrap();
+
+    let (allocations, bytes, elapsed) = measure(|| {
+        semantic.embed(TEXT).unwrap();
+    });
+    println!("embed:           {:8.1} allocations {:10.0} bytes {:?} per call", allocations, bytes, elapsed);
+
+    let (allocations, bytes, elapsed) = measure(|| {
+        semantic.embed_batch(&[TEXT]).unwrap();
+    });
+    println!("embed_batch(1):  {:8.1} allocations {:10.0} bytes {:?} per call", allocations, bytes, elapsed);
+}
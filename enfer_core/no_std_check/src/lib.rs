@@ -0,0 +1,12 @@
+//! Compiles `enfer_core/src/embedding/math.rs` on its own in a `no_std + alloc` crate, so anything in it that needs
+//! `std` fails this build. Build it for a target without `std` to be sure nothing sneaks in through the prelude.
+
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+// the module's functions are crate-internal in enfer_core
+#![allow(dead_code)]
+
+extern crate alloc;
+
+#[path = "../../src/embedding/math.rs"]
+mod math;
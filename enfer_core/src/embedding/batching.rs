@@ -0,0 +1,60 @@
+/// Groups sequences of similar length so each group pads to little more than its own longest sequence.
+///
+/// Indices are sorted by length and split greedily so that a group's padded size, its number of sequences times its
+/// longest length, stays within `max_batch_tokens`. A sequence longer than the budget on its own gets a group of its
+/// own. Every index appears in exactly one group, so results can be put back in input order.
+pub(crate) fn plan_sub_batches(lengths: &[usize], max_batch_tokens: usize) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    order.sort_by_key(|index| lengths[*index]);
+
+    let mut batches = vec![];
+    let mut current: Vec<usize> = vec![];
+    for index in order {
+        // sorted ascending, so the sequence being added is the longest of the group
+        let padded = (current.len() + 1) * lengths[index];
+        if !current.is_empty() && padded > max_batch_tokens {
+            batches.push(std::mem::take(&mut current));
+        }
+        current.push(index);
+    }
+
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_group_similar_lengths_within_budget() {
+        let lengths = [100, 5, 6, 98, 4];
+
+        let batches = plan_sub_batches(&lengths, 200);
+
+        assert_eq!(batches, vec![vec![4, 1, 2], vec![3, 0]]);
+        for batch in &batches {
+            let longest = batch.iter().map(|index| lengths[*index]).max().unwrap();
+            assert!(batch.len() * longest <= 200);
+        }
+    }
+
+    #[test]
+    fn should_cover_every_index_once() {
+        let lengths = [7, 512, 3, 3, 40, 9];
+
+        let mut covered: Vec<usize> = plan_sub_batches(&lengths, 64).into_iter().flatten().collect();
+        covered.sort();
+
+        assert_eq!(covered, (0..lengths.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn should_isolate_sequences_over_budget() {
+        assert_eq!(plan_sub_batches(&[300, 2], 100), vec![vec![1], vec![0]]);
+        assert!(plan_sub_batches(&[], 100).is_empty());
+    }
+}
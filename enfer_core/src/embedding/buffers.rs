@@ -0,0 +1,118 @@
+use std::sync::{Mutex, PoisonError};
+
+use tokenizers::Encoding;
+
+/// The `i64` model inputs of one sequence, kept around so their allocations can be reused by the next call. This
+/// saves the three `Vec<i64>` allocations of every single-sequence call, the tensors handed to ORT are still copied
+/// from them on every run, see `benches/embed_allocations.rs`.
+#[derive(Debug, Default)]
+pub(crate) struct InputBuffers {
+    pub(crate) input_ids: Vec<i64>,
+    pub(crate) attention_mask: Vec<i64>,
+    pub(crate) token_type_ids: Vec<i64>,
+}
+
+impl InputBuffers {
+    /// Overwrites the buffers with the encoding, only growing them when it's longer than any previous one.
+    pub(crate) fn fill(&mut self, encoding: &Encoding) {
+        fill(&mut self.input_ids, encoding.get_ids());
+        fill(&mut self.attention_mask, encoding.get_attention_mask());
+        fill(&mut self.token_type_ids, encoding.get_type_ids());
+    }
+
+    /// Overwrites the buffers with ids tokenized elsewhere, attending to every token when there is no mask and
+    /// using `0` as the token type of every token.
+    pub(crate) fn fill_ids(&mut self, input_ids: &[i64], attention_mask: Option<&[i64]>) {
+        self.input_ids.clear();
+        self.input_ids.extend_from_slice(input_ids);
+
+        self.attention_mask.clear();
+        match attention_mask {
+            Some(mask) => self.attention_mask.extend_from_slice(mask),
+            None => self.attention_mask.resize(input_ids.len(), 1),
+        }
+
+        self.token_type_ids.clear();
+        self.token_type_ids.resize(input_ids.len(), 0);
+    }
+}
+
+fn fill(buffer: &mut Vec<i64>, values: &[u32]) {
+    buffer.clear();
+    buffer.extend(values.iter().map(|value| *value as i64));
+}
+
+/// A pool of [`InputBuffers`] shared by every caller of a [`crate::Semantic`].
+///
+/// Each call takes its own buffers out of the pool and gives them back when done, so concurrent callers never
+/// share a buffer, and the pool grows to at most as many buffers as there were calls in flight at once.
+#[derive(Debug, Default)]
+pub(crate) struct BufferPool {
+    buffers: Mutex<Vec<InputBuffers>>,
+}
+
+impl BufferPool {
+    pub(crate) fn take(&self) -> InputBuffers {
+        // the buffers are overwritten before use, so a panic while one was taken can't leave the pool inconsistent
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner).pop().unwrap_or_default()
+    }
+
+    pub(crate) fn give_back(&self, buffers: InputBuffers) {
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner).push(buffers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_reuse_returned_buffers() {
+        let pool = BufferPool::default();
+
+        let mut buffers = pool.take();
+        buffers.input_ids.extend([101, 7592, 102]);
+        let capacity = buffers.input_ids.capacity();
+        pool.give_back(buffers);
+
+        let buffers = pool.take();
+        assert_eq!(buffers.input_ids.capacity(), capacity);
+        assert!(pool.take().input_ids.is_empty());
+    }
+
+    #[test]
+    fn should_recover_from_poisoned_lock() {
+        let pool = BufferPool::default();
+        let _ = std::panic::catch_unwind(|| {
+            let _guard = pool.buffers.lock().unwrap();
+            panic!("poison the lock");
+        });
+        assert!(pool.buffers.is_poisoned());
+
+        pool.give_back(InputBuffers::default());
+        pool.take();
+    }
+
+    #[test]
+    fn should_overwrite_previous_contents() {
+        let mut buffer = vec![1, 2, 3, 4];
+
+        fill(&mut buffer, &[7, 8]);
+
+        assert_eq!(buffer, vec![7, 8]);
+    }
+
+    #[test]
+    fn should_default_mask_and_type_ids_for_raw_ids() {
+        let mut buffers = InputBuffers::default();
+
+        buffers.fill_ids(&[101, 7592, 102], None);
+        assert_eq!(buffers.attention_mask, vec![1, 1, 1]);
+        assert_eq!(buffers.token_type_ids, vec![0, 0, 0]);
+
+        buffers.fill_ids(&[101, 102], Some(&[1, 0]));
+        assert_eq!(buffers.input_ids, vec![101, 102]);
+        assert_eq!(buffers.attention_mask, vec![1, 0]);
+        assert_eq!(buffers.token_type_ids, vec![0, 0]);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::embedding::config::{LogLevel, OptimizationLevel, PaddingSide, Precision, SemanticConfig};
+use crate::embedding::inputs::InputNames;
+use crate::embedding::outputs::OutputSelector;
+use crate::embedding::pool::SemanticPool;
+use crate::embedding::pooling::{PoolingScope, PoolingStrategy};
+use crate::embedding::provider::ExecutionProvider;
+use crate::embedding::semantic::{Semantic, SemanticError};
+use crate::embedding::tokenizer::{TextNormalizer, TokenizerFormat};
+
+/// Builds a [`Semantic`] from the model and tokenizer bytes, with chainable setters for every option.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{PoolingStrategy, SemanticBuilder};
+///
+/// let model = std::fs::read("../model/model.onnx").unwrap();
+/// let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+///
+/// let semantic = SemanticBuilder::new(model, tokenizer_data)
+///     .pooling(PoolingStrategy::Cls)
+///     .normalize(true)
+///     .intra_threads(4)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct SemanticBuilder {
+    model: Vec<u8>,
+    tokenizer_data: Vec<u8>,
+    config: SemanticConfig,
+    pool_size: usize,
+}
+
+impl SemanticBuilder {
+    pub fn new(model: Vec<u8>, tokenizer_data: Vec<u8>) -> Self {
+        SemanticBuilder {
+            model,
+            tokenizer_data,
+            config: SemanticConfig::default(),
+            pool_size: 1,
+        }
+    }
+
+    /// Replaces all options at once.
+    pub fn config(mut self, config: SemanticConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// See [`SemanticConfig::intra_threads`].
+    pub fn intra_threads(mut self, threads: i16) -> Self {
+        self.config.intra_threads = Some(threads);
+        self
+    }
+
+    /// See [`SemanticConfig::inter_threads`].
+    pub fn inter_threads(mut self, threads: i16) -> Self {
+        self.config.inter_threads = Some(threads);
+        self
+    }
+
+    /// See [`SemanticConfig::optimization_level`].
+    pub fn optimization_level(mut self, optimization_level: OptimizationLevel) -> Self {
+        self.config.optimization_level = optimization_level;
+        self
+    }
+
+    /// See [`SemanticConfig::enable_memory_pattern`].
+    pub fn enable_memory_pattern(mut self, enable_memory_pattern: bool) -> Self {
+        self.config.enable_memory_pattern = enable_memory_pattern;
+        self
+    }
+
+    /// See [`SemanticConfig::pooling`].
+    pub fn pooling(mut self, pooling: PoolingStrategy) -> Self {
+        self.config.pooling = pooling;
+        self
+    }
+
+    /// See [`SemanticConfig::pooling_scope`].
+    pub fn pooling_scope(mut self, pooling_scope: PoolingScope) -> Self {
+        self.config.pooling_scope = pooling_scope;
+        self
+    }
+
+    /// See [`SemanticConfig::normalize`].
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.config.normalize = normalize;
+        self
+    }
+
+    /// See [`SemanticConfig::output_dim`].
+    pub fn output_dim(mut self, dim: usize) -> Self {
+        self.config.output_dim = Some(dim);
+        self
+    }
+
+    /// See [`SemanticConfig::execution_provider`].
+    pub fn execution_provider(mut self, execution_provider: ExecutionProvider) -> Self {
+        self.config.execution_provider = execution_provider;
+        self
+    }
+
+    /// See [`SemanticConfig::compute_precision`].
+    pub fn compute_precision(mut self, compute_precision: Precision) -> Self {
+        self.config.compute_precision = compute_precision;
+        self
+    }
+
+    /// See [`SemanticConfig::tokenizer_format`].
+    pub fn tokenizer_format(mut self, tokenizer_format: TokenizerFormat) -> Self {
+        self.config.tokenizer_format = tokenizer_format;
+        self
+    }
+
+    /// See [`SemanticConfig::input_names`].
+    pub fn input_names(mut self, input_names: InputNames) -> Self {
+        self.config.input_names = input_names;
+        self
+    }
+
+    /// See [`SemanticConfig::output`].
+    pub fn output(mut self, output: OutputSelector) -> Self {
+        self.config.output = output;
+        self
+    }
+
+    /// See [`SemanticConfig::max_length`].
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.config.max_length = max_length;
+        self
+    }
+
+    /// See [`SemanticConfig::truncate`].
+    pub fn truncate(mut self, truncate: bool) -> Self {
+        self.config.truncate = truncate;
+        self
+    }
+
+    /// See [`SemanticConfig::text_normalizers`].
+    pub fn text_normalizers(mut self, text_normalizers: Vec<TextNormalizer>) -> Self {
+        self.config.text_normalizers = text_normalizers;
+        self
+    }
+
+    /// See [`SemanticConfig::add_special_tokens`].
+    pub fn add_special_tokens(mut self, add_special_tokens: bool) -> Self {
+        self.config.add_special_tokens = add_special_tokens;
+        self
+    }
+
+    /// See [`SemanticConfig::max_batch_tokens`].
+    pub fn max_batch_tokens(mut self, max_batch_tokens: usize) -> Self {
+        self.config.max_batch_tokens = Some(max_batch_tokens);
+        self
+    }
+
+    /// See [`SemanticConfig::pad_token_id`].
+    pub fn pad_token_id(mut self, pad_token_id: u32) -> Self {
+        self.config.pad_token_id = Some(pad_token_id);
+        self
+    }
+
+    /// See [`SemanticConfig::padding_side`].
+    pub fn padding_side(mut self, padding_side: PaddingSide) -> Self {
+        self.config.padding_side = padding_side;
+        self
+    }
+
+    /// See [`SemanticConfig::deterministic`].
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.config.deterministic = deterministic;
+        self
+    }
+
+    /// See [`SemanticConfig::validate`].
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.config.validate = validate;
+        self
+    }
+
+    /// See [`SemanticConfig::collect_stats`].
+    pub fn collect_stats(mut self, collect_stats: bool) -> Self {
+        self.config.collect_stats = collect_stats;
+        self
+    }
+
+    /// See [`SemanticConfig::warmup`].
+    pub fn warmup(mut self, warmup: bool) -> Self {
+        self.config.warmup = warmup;
+        self
+    }
+
+    /// See [`SemanticConfig::log_level`].
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.config.log_level = log_level;
+        self
+    }
+
+    /// See [`SemanticConfig::query_prefix`].
+    pub fn query_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.query_prefix = prefix.into();
+        self
+    }
+
+    /// See [`SemanticConfig::passage_prefix`].
+    pub fn passage_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.passage_prefix = prefix.into();
+        self
+    }
+
+    pub fn build(self) -> Result<Semantic, SemanticError> {
+        Semantic::init_semantic_with_config(self.model, self.tokenizer_data, self.config)
+    }
+
+    pub async fn build_async(self) -> Result<Semantic, SemanticError> {
+        self.build()
+    }
+
+    /// The number of sessions [`SemanticBuilder::build_pool`] creates, `1` by default.
+    pub fn pool_size(mut self, pool_size: usize) -> Self {
+        self.pool_size = pool_size;
+        self
+    }
+
+    /// Builds a [`SemanticPool`] of `pool_size` sessions sharing these options, fails with
+    /// [`SemanticError::InvalidPoolSize`] when the size is `0`.
+    pub fn build_pool(self) -> Result<SemanticPool, SemanticError> {
+        SemanticPool::new(self.model, self.tokenizer_data, self.config, self.pool_size)
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::Read;
+
+use crate::embedding::semantic::{Semantic, SemanticError};
+
+/// The file names looked up in a bundle, wherever they are in the archive.
+pub const BUNDLE_MODEL_FILE: &str = "model.onnx";
+pub const BUNDLE_TOKENIZER_FILE: &str = "tokenizer.json";
+
+impl Semantic {
+    /// Initializes a [`Semantic`] from a single uncompressed tar archive containing both the model and the tokenizer,
+    /// so only one asset has to be shipped or fetched. The archive must contain a `model.onnx` and a `tokenizer.json`,
+    /// either at its root or in a directory:
+    ///
+    /// ```text
+    /// bundle.tar
+    /// ├── model.onnx
+    /// └── tokenizer.json
+    /// ```
+    ///
+    /// Such an archive is created with `tar -cf bundle.tar model.onnx tokenizer.json`. Needs the `bundle` feature.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::Semantic;
+    ///
+    /// let bundle = std::fs::read("../model/bundle.tar").unwrap();
+    /// let semantic = Semantic::from_bundle(&bundle).unwrap();
+    /// ```
+    pub fn from_bundle(bytes: &[u8]) -> Result<Semantic, SemanticError> {
+        let (model, tokenizer_data) = read_bundle(bytes)?;
+
+        Self::init_semantic(model, tokenizer_data)
+    }
+}
+
+/// Extracts the model and tokenizer bytes from the archive.
+fn read_bundle(bytes: &[u8]) -> Result<(Vec<u8>, Vec<u8>), SemanticError> {
+    let mut archive = tar::Archive::new(bytes);
+    let mut model = None;
+    let mut tokenizer_data = None;
+
+    for entry in archive.entries().map_err(SemanticError::BundleReadError)? {
+        let mut entry = entry.map_err(SemanticError::BundleReadError)?;
+        let path = entry.path().map_err(SemanticError::BundleReadError)?;
+
+        let target = match path.file_name().and_then(|name| name.to_str()) {
+            Some(BUNDLE_MODEL_FILE) => &mut model,
+            Some(BUNDLE_TOKENIZER_FILE) => &mut tokenizer_data,
+            _ => continue,
+        };
+
+        // the size comes from the header, so a corrupt one could claim more than the whole archive
+        let mut data = Vec::with_capacity((entry.size() as usize).min(bytes.len()));
+        entry.read_to_end(&mut data).map_err(SemanticError::BundleReadError)?;
+        *target = Some(data);
+    }
+
+    let model = model.ok_or_else(|| SemanticError::BundleMissingFile(BUNDLE_MODEL_FILE.to_string()))?;
+    let tokenizer_data = tokenizer_data.ok_or_else(|| SemanticError::BundleMissingFile(BUNDLE_TOKENIZER_FILE.to_string()))?;
+
+    Ok((model, tokenizer_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn should_extract_model_and_tokenizer() {
+        let bytes = bundle(&[("README.md", b"docs"), ("minilm/model.onnx", b"onnx"), ("minilm/tokenizer.json", b"{}")]);
+
+        let (model, tokenizer_data) = read_bundle(&bytes).unwrap();
+
+        assert_eq!(model, b"onnx");
+        assert_eq!(tokenizer_data, b"{}");
+    }
+
+    #[test]
+    fn should_reject_entry_larger_than_archive() {
+        let mut header = tar::Header::new_gnu();
+        header.set_path("model.onnx").unwrap();
+        header.set_size(1 << 40);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let bytes = [header.as_bytes().as_slice(), b"onnx"].concat();
+
+        assert!(read_bundle(&bytes).is_err());
+    }
+
+    #[test]
+    fn should_report_missing_file() {
+        let bytes = bundle(&[("model.onnx", b"onnx")]);
+
+        assert!(matches!(read_bundle(&bytes), Err(SemanticError::BundleMissingFile(name)) if name == "tokenizer.json"));
+    }
+}
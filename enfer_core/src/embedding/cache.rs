@@ -0,0 +1,206 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::embedding::Embedding;
+use crate::embedding::semantic::{Semantic, SemanticError};
+
+/// Wraps a [`Semantic`] with a least-recently-used cache keyed on the input text, so repeated inputs skip inference.
+///
+/// The cache is shared by every caller. The lock is not held while embedding, so two threads missing on the same
+/// text at once both run inference and the last one to finish fills the cache.
+///
+/// Example:
+/// ```rust
+/// use inference_core::Semantic;
+///
+/// let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap().with_cache(1024);
+/// let first = semantic.embed("Hello world!").unwrap();
+/// let second = semantic.embed("Hello world!").unwrap();
+/// assert_eq!(semantic.inference_count(), 1);
+/// ```
+pub struct CachedSemantic {
+    semantic: Semantic,
+    cache: Mutex<LruCache>,
+    inference_count: AtomicU64,
+}
+
+impl CachedSemantic {
+    /// Caches up to `capacity` embeddings, a capacity of `0` disables caching.
+    pub fn new(semantic: Semantic, capacity: usize) -> Self {
+        CachedSemantic {
+            semantic,
+            cache: Mutex::new(LruCache::new(capacity)),
+            inference_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Same as [`Semantic::embed`], but returns the cached embedding when the exact same text was embedded before.
+    pub fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        if let Some(embedding) = self.lock().get(sequence) {
+            return Ok(embedding);
+        }
+
+        self.inference_count.fetch_add(1, Ordering::Relaxed);
+        let embedding = self.semantic.embed(sequence)?;
+        self.lock().put(sequence.to_string(), embedding.clone());
+
+        Ok(embedding)
+    }
+
+    /// Drops every cached embedding.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Changes how many embeddings are kept, evicting the least recently used ones if needed. `0` disables caching.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.lock().set_capacity(capacity);
+    }
+
+    /// The number of embeddings currently cached.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many calls missed the cache and ran inference.
+    pub fn inference_count(&self) -> u64 {
+        self.inference_count.load(Ordering::Relaxed)
+    }
+
+    /// The wrapped [`Semantic`], for the calls that bypass the cache.
+    pub fn semantic(&self) -> &Semantic {
+        &self.semantic
+    }
+
+    /// The cache only holds plain data, so it's still usable after a panic while it was locked.
+    fn lock(&self) -> MutexGuard<'_, LruCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Semantic {
+    /// Wraps this [`Semantic`] in a [`CachedSemantic`] holding up to `capacity` embeddings.
+    pub fn with_cache(self, capacity: usize) -> CachedSemantic {
+        CachedSemantic::new(self, capacity)
+    }
+}
+
+/// Entries are stamped with an increasing tick on every access, and `order` maps ticks back to keys
+/// so the least recently used entry is always the first one.
+struct LruCache {
+    capacity: usize,
+    entries: HashMap<String, (Embedding, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        LruCache { capacity, entries: HashMap::new(), order: BTreeMap::new(), tick: 0 }
+    }
+
+    fn get(&mut self, key: &str) -> Option<Embedding> {
+        let tick = self.next_tick();
+        let (embedding, last_used) = self.entries.get_mut(key)?;
+
+        let key = self.order.remove(last_used)?;
+        self.order.insert(tick, key);
+        *last_used = tick;
+
+        Some(embedding.clone())
+    }
+
+    fn put(&mut self, key: String, embedding: Embedding) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let tick = self.next_tick();
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (embedding, tick)) {
+            self.order.remove(&last_used);
+        }
+        self.order.insert(tick, key);
+
+        self.evict();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.order.pop_first() {
+                Some((_, key)) => self.entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedding(value: f32) -> Embedding {
+        Embedding(vec![value; 3])
+    }
+
+    #[test]
+    fn should_evict_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.put("a".to_string(), embedding(1.0));
+        cache.put("b".to_string(), embedding(2.0));
+
+        assert!(cache.get("a").is_some());
+        cache.put("c".to_string(), embedding(3.0));
+
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().0, embedding(1.0).0);
+        assert_eq!(cache.get("c").unwrap().0, embedding(3.0).0);
+    }
+
+    #[test]
+    fn should_not_cache_with_zero_capacity() {
+        let mut cache = LruCache::new(0);
+        cache.put("a".to_string(), embedding(1.0));
+
+        assert_eq!(cache.len(), 0);
+        assert!(cache.get("a").is_none());
+    }
+
+    #[test]
+    fn should_shrink_and_clear() {
+        let mut cache = LruCache::new(3);
+        for (key, value) in [("a", 1.0), ("b", 2.0), ("c", 3.0)] {
+            cache.put(key.to_string(), embedding(value));
+        }
+
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("c").is_some());
+
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -0,0 +1,394 @@
+use crate::embedding::inputs::InputNames;
+use crate::embedding::outputs::OutputSelector;
+use crate::embedding::pooling::{PoolingScope, PoolingStrategy};
+use crate::embedding::provider::ExecutionProvider;
+use crate::embedding::semantic::SemanticError;
+use crate::embedding::tokenizer::{TextNormalizer, TokenizerFormat};
+
+/// How aggressively ORT rewrites the model graph when the session is created. Some models produce wrong
+/// outputs or fail to load with the extended fusions of `Level3`, and only load at a lower level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OptimizationLevel {
+    Disable,
+    /// Basic optimizations such as constant folding and redundant node elimination.
+    Level1,
+    /// Level1 plus extended node fusions.
+    Level2,
+    /// Every optimization, including layout changes.
+    #[default]
+    Level3,
+}
+
+impl From<OptimizationLevel> for ort::GraphOptimizationLevel {
+    fn from(level: OptimizationLevel) -> Self {
+        match level {
+            OptimizationLevel::Disable => ort::GraphOptimizationLevel::Disable,
+            OptimizationLevel::Level1 => ort::GraphOptimizationLevel::Level1,
+            OptimizationLevel::Level2 => ort::GraphOptimizationLevel::Level2,
+            OptimizationLevel::Level3 => ort::GraphOptimizationLevel::Level3,
+        }
+    }
+}
+
+/// The minimum severity of the messages ONNX Runtime logs.
+///
+/// ORT's messages are emitted as `tracing` events with the `ort` target. With the `ort-log` feature they are also
+/// forwarded to the `log` crate whenever no `tracing` subscriber is installed, so they end up in the same logger
+/// as the rest of the application.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogLevel {
+    Verbose,
+    Info,
+    #[default]
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl From<LogLevel> for ort::LoggingLevel {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Verbose => ort::LoggingLevel::Verbose,
+            LogLevel::Info => ort::LoggingLevel::Info,
+            LogLevel::Warning => ort::LoggingLevel::Warning,
+            LogLevel::Error => ort::LoggingLevel::Error,
+            LogLevel::Fatal => ort::LoggingLevel::Fatal,
+        }
+    }
+}
+
+/// The floating point precision the model is computed in. Only TensorRT can compute an fp32 model in fp16, the other
+/// execution providers keep running it in fp32.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Precision {
+    #[default]
+    Fp32,
+    /// Half precision, faster and lighter on supporting GPUs at a small cost in accuracy.
+    Fp16,
+}
+
+/// Which end of the shorter sequences [`crate::Semantic::embed_batch`] pads. Encoders such as BERT expect right
+/// padding, some decoder-style models expect left padding. The attention mask is padded on the same side, so pooling
+/// ignores the padding either way, but models with absolute positions shift the left-padded sequences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaddingSide {
+    #[default]
+    Right,
+    Left,
+}
+
+impl PaddingSide {
+    /// Appends `values` to `out`, with `padding` copies of `pad` on this side.
+    pub(crate) fn extend_padded(self, out: &mut Vec<i64>, values: impl Iterator<Item = i64>, padding: usize, pad: i64) {
+        let pads = std::iter::repeat(pad).take(padding);
+
+        match self {
+            PaddingSide::Right => out.extend(values.chain(pads)),
+            PaddingSide::Left => out.extend(pads.chain(values)),
+        }
+    }
+}
+
+/// Options used to build a [`crate::Semantic`].
+///
+/// Example:
+/// ```rust
+/// use inference_core::SemanticConfig;
+///
+/// let config = SemanticConfig { intra_threads: Some(4), ..Default::default() };
+/// ```
+#[derive(Debug, Clone)]
+pub struct SemanticConfig {
+    /// Number of threads ORT uses to parallelize a single operator. When `None`, the `NUM_OMP_THREADS`
+    /// environment variable is used, and `1` if it isn't set. Always `1` with the `wasm` feature.
+    pub intra_threads: Option<i16>,
+    /// Number of threads ORT uses to run independent branches of the graph at the same time. When set, the session
+    /// switches to parallel execution, which only pays off for models with parallel branches. When `None`, ORT's
+    /// default sequential execution is kept. Ignored with the `wasm` feature and in deterministic mode.
+    pub inter_threads: Option<i16>,
+    /// Runs the model on the CPU, on a single thread with sequential execution and at most `Level1` graph
+    /// optimizations, so the same input always produces bit-identical embeddings instead of floats reduced in
+    /// whichever order the threads finish or by whichever kernels a GPU or a fusion picks. Overrides
+    /// `intra_threads`, `execution_provider` and higher optimization levels, and trades the speedup of accelerated,
+    /// multi-threaded inference for reproducibility.
+    pub deterministic: bool,
+    /// The graph optimization level of the session, `Level3` by default.
+    pub optimization_level: OptimizationLevel,
+    /// Lets ORT plan the memory of a run from the runs before it, on by default. With fixed input shapes this saves
+    /// allocations, but a plan is kept per shape, and with the varying batch sizes and sequence lengths of a
+    /// long-running service the arena keeps growing. Turning it off keeps the memory flat at the cost of a few more
+    /// allocations per run. DirectML requires it to be off.
+    pub enable_memory_pattern: bool,
+    /// How token embeddings are pooled into one embedding.
+    pub pooling: PoolingStrategy,
+    /// Which tokens are pooled, all of them by default.
+    pub pooling_scope: PoolingScope,
+    /// Whether embeddings are L2-normalized before they are returned.
+    pub normalize: bool,
+    /// Keeps only the first `output_dim` dimensions of every embedding, before normalizing it. Models trained with
+    /// Matryoshka representation learning stay accurate at a fraction of their hidden size. Must be between `1` and
+    /// the hidden size, `None` keeps every dimension.
+    pub output_dim: Option<usize>,
+    /// Which backend runs the model, CPU by default.
+    pub execution_provider: ExecutionProvider,
+    /// The precision requested from the execution provider, `Fp32` by default. Only TensorRT computes in fp16, building
+    /// its engine in half precision. With any other provider, including the ones TensorRT falls back to, the session
+    /// falls back to fp32 with a logged warning, see [`crate::Semantic::compute_precision`].
+    pub compute_precision: Precision,
+    /// How the tokenizer bytes are read, detected from the bytes by default.
+    pub tokenizer_format: TokenizerFormat,
+    /// Normalizations applied to every text before it is tokenized, none by default. Use them to match the
+    /// preprocessing the model was trained with when its tokenizer doesn't apply it, e.g. accent stripping.
+    pub text_normalizers: Vec<TextNormalizer>,
+    /// The model input names the tokenizer outputs are bound to.
+    pub input_names: InputNames,
+    /// The model output that is pooled into the embedding.
+    pub output: OutputSelector,
+    /// The maximum number of tokens fed to the model, including special tokens.
+    pub max_length: usize,
+    /// Whether inputs longer than `max_length` are silently truncated, or rejected with
+    /// [`SemanticError::InputTooLong`].
+    pub truncate: bool,
+    /// Whether the tokenizer adds the model's special tokens, such as `[CLS]` and `[SEP]`, which changes the pooled
+    /// embedding. Enabled by default, as most embedding models are trained with them.
+    pub add_special_tokens: bool,
+    /// When set, [`crate::Semantic::embed_batch`] groups sequences of similar length into sub-batches whose padded
+    /// size (sequences times longest length) stays within this many tokens. `None` pads the whole batch at once.
+    pub max_batch_tokens: Option<usize>,
+    /// The token id batched sequences are padded with. When `None`, the tokenizer's padding configuration is used,
+    /// then its `[PAD]` or `<pad>` token, and batches that need padding fail with [`SemanticError::NoPadToken`]
+    /// when it has none. Pad positions are masked out of the attention mask either way, so the id only has to be
+    /// one the model accepts.
+    pub pad_token_id: Option<u32>,
+    /// Which side batched sequences are padded on, `Right` by default.
+    pub padding_side: PaddingSide,
+    /// Whether the tokenizer's vocabulary is checked against the model's token embedding table while building,
+    /// failing with [`SemanticError::ModelTokenizerMismatch`] when the tokenizer belongs to another model.
+    /// Enabled by default.
+    pub validate: bool,
+    /// Whether a dummy inference runs while building, so ORT's lazy allocations don't slow down the first real call.
+    pub warmup: bool,
+    /// Whether the token count of every tokenized sequence is recorded for [`crate::Semantic::stats`]. Disabled
+    /// by default.
+    pub collect_stats: bool,
+    /// The verbosity of ONNX Runtime's own logs, `Warning` by default. The ORT environment is global, so the
+    /// level is set by whichever [`crate::Semantic`] creates it first.
+    pub log_level: LogLevel,
+    /// Prepended to the text by [`crate::Semantic::embed_query`], e.g. `"query: "` for E5 models. Empty by default.
+    pub query_prefix: String,
+    /// Prepended to the text by [`crate::Semantic::embed_passage`], e.g. `"passage: "` for E5 models. Empty by default.
+    pub passage_prefix: String,
+}
+
+impl Default for SemanticConfig {
+    fn default() -> Self {
+        SemanticConfig {
+            intra_threads: None,
+            inter_threads: None,
+            deterministic: false,
+            optimization_level: OptimizationLevel::default(),
+            enable_memory_pattern: true,
+            pooling: PoolingStrategy::default(),
+            pooling_scope: PoolingScope::default(),
+            normalize: false,
+            output_dim: None,
+            execution_provider: ExecutionProvider::default(),
+            compute_precision: Precision::default(),
+            tokenizer_format: TokenizerFormat::default(),
+            text_normalizers: vec![],
+            input_names: InputNames::default(),
+            output: OutputSelector::default(),
+            max_length: 512,
+            truncate: true,
+            add_special_tokens: true,
+            max_batch_tokens: None,
+            pad_token_id: None,
+            padding_side: PaddingSide::default(),
+            validate: true,
+            warmup: false,
+            collect_stats: false,
+            log_level: LogLevel::default(),
+            query_prefix: String::new(),
+            passage_prefix: String::new(),
+        }
+    }
+}
+
+impl SemanticConfig {
+    /// Resolves the intra-op thread count, an explicit value takes precedence over `NUM_OMP_THREADS`. Always `1`
+    /// in deterministic mode.
+    pub(crate) fn resolve_intra_threads(&self) -> Result<i16, SemanticError> {
+        let threads = match self.intra_threads {
+            Some(threads) => threads,
+            None => default_intra_threads(),
+        };
+
+        if threads <= 0 {
+            return Err(SemanticError::InvalidThreadCount(threads));
+        }
+
+        // the browser has no thread pool to spread operators over
+        if cfg!(feature = "wasm") || self.deterministic {
+            return Ok(1);
+        }
+
+        Ok(threads)
+    }
+
+    /// The graph optimization level, lowered to `Level1` in deterministic mode as the fusions of the higher levels
+    /// pick kernels depending on the hardware.
+    pub(crate) fn resolve_optimization_level(&self) -> OptimizationLevel {
+        match self.optimization_level {
+            OptimizationLevel::Level2 | OptimizationLevel::Level3 if self.deterministic => OptimizationLevel::Level1,
+            level => level,
+        }
+    }
+
+    /// The execution provider to register, always the CPU in deterministic mode.
+    pub(crate) fn resolve_execution_provider(&self) -> ExecutionProvider {
+        if self.deterministic && self.execution_provider != ExecutionProvider::Cpu {
+            log::warn!("running on the CPU instead of the {:?} execution provider in deterministic mode", self.execution_provider);
+            return ExecutionProvider::Cpu;
+        }
+
+        self.execution_provider.clone()
+    }
+
+    /// Resolves the inter-op thread count, `None` when the session should keep executing sequentially.
+    pub(crate) fn resolve_inter_threads(&self) -> Result<Option<i16>, SemanticError> {
+        match self.inter_threads {
+            Some(threads) if threads <= 0 => Err(SemanticError::InvalidThreadCount(threads)),
+            _ if cfg!(feature = "wasm") || self.deterministic => Ok(None),
+            threads => Ok(threads),
+        }
+    }
+}
+
+/// Per-call overrides for [`crate::Semantic::embed_with_options`], every option left `None` keeps the value the
+/// [`crate::Semantic`] was built with.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{EmbedOptions, PoolingStrategy};
+///
+/// let options = EmbedOptions { pooling: Some(PoolingStrategy::Cls), normalize: Some(true), ..Default::default() };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmbedOptions {
+    /// Overrides [`SemanticConfig::pooling`].
+    pub pooling: Option<PoolingStrategy>,
+    /// Overrides [`SemanticConfig::normalize`].
+    pub normalize: Option<bool>,
+    /// Prepended to the text, e.g. `"query: "` for E5 models.
+    pub prefix: Option<String>,
+    /// Lowers [`SemanticConfig::max_length`] for this call, longer inputs are truncated or rejected as configured by
+    /// [`SemanticConfig::truncate`]. It can't raise the limit the tokenizer was set up with.
+    pub max_length: Option<usize>,
+}
+
+#[cfg(not(feature = "wasm"))]
+fn default_intra_threads() -> i16 {
+    match std::env::var("NUM_OMP_THREADS") {
+        Ok(v) => str::parse(&v).unwrap_or(1),
+        Err(_) => 1,
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn default_intra_threads() -> i16 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "wasm"))]
+    fn should_prefer_explicit_thread_count() {
+        let config = SemanticConfig { intra_threads: Some(4), ..Default::default() };
+
+        assert_eq!(config.resolve_intra_threads().unwrap(), 4);
+    }
+
+    #[test]
+    fn should_lower_optimization_level_when_deterministic() {
+        let config = SemanticConfig { deterministic: true, ..Default::default() };
+        let unoptimized = SemanticConfig { optimization_level: OptimizationLevel::Disable, ..config.clone() };
+
+        assert_eq!(config.resolve_optimization_level(), OptimizationLevel::Level1);
+        assert_eq!(unoptimized.resolve_optimization_level(), OptimizationLevel::Disable);
+        assert_eq!(SemanticConfig::default().resolve_optimization_level(), OptimizationLevel::Level3);
+        assert_eq!(config.resolve_execution_provider(), ExecutionProvider::Cpu);
+    }
+
+    #[test]
+    fn should_force_single_thread_when_deterministic() {
+        let config = SemanticConfig { intra_threads: Some(4), deterministic: true, ..Default::default() };
+
+        assert_eq!(config.resolve_intra_threads().unwrap(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "wasm")]
+    fn should_force_single_thread_for_wasm() {
+        let config = SemanticConfig { intra_threads: Some(4), ..Default::default() };
+
+        assert_eq!(config.resolve_intra_threads().unwrap(), 1);
+    }
+
+    #[test]
+    fn should_default_to_full_optimization() {
+        let config = SemanticConfig::default();
+
+        assert_eq!(config.optimization_level, OptimizationLevel::Level3);
+        assert!(matches!(OptimizationLevel::Disable.into(), ort::GraphOptimizationLevel::Disable));
+    }
+
+    #[test]
+    fn should_log_warnings_by_default() {
+        let config = SemanticConfig::default();
+
+        assert_eq!(config.log_level, LogLevel::Warning);
+        assert!(matches!(LogLevel::Verbose.into(), ort::LoggingLevel::Verbose));
+    }
+
+    #[test]
+    fn should_align_ids_and_mask_when_left_padding() {
+        let (mut ids, mut mask) = (vec![], vec![]);
+        for sequence in [vec![101, 7, 102], vec![101, 102]] {
+            let padding = 3 - sequence.len();
+            PaddingSide::Left.extend_padded(&mut ids, sequence.iter().copied(), padding, 0);
+            PaddingSide::Left.extend_padded(&mut mask, sequence.iter().map(|_| 1), padding, 0);
+        }
+
+        assert_eq!(ids, vec![101, 7, 102, 0, 101, 102]);
+        assert_eq!(mask, vec![1, 1, 1, 0, 1, 1]);
+        assert_eq!(SemanticConfig::default().padding_side, PaddingSide::Right);
+    }
+
+    #[test]
+    fn should_reject_non_positive_thread_count() {
+        for threads in [0, -2] {
+            let config = SemanticConfig { intra_threads: Some(threads), ..Default::default() };
+
+            assert!(matches!(config.resolve_intra_threads(), Err(SemanticError::InvalidThreadCount(t)) if t == threads));
+
+            let config = SemanticConfig { inter_threads: Some(threads), ..Default::default() };
+
+            assert!(matches!(config.resolve_inter_threads(), Err(SemanticError::InvalidThreadCount(t)) if t == threads));
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "wasm"))]
+    fn should_only_run_in_parallel_when_inter_threads_are_set() {
+        assert_eq!(SemanticConfig::default().resolve_inter_threads().unwrap(), None);
+
+        let config = SemanticConfig { inter_threads: Some(2), ..Default::default() };
+        assert_eq!(config.resolve_inter_threads().unwrap(), Some(2));
+
+        let config = SemanticConfig { inter_threads: Some(2), deterministic: true, ..Default::default() };
+        assert_eq!(config.resolve_inter_threads().unwrap(), None);
+    }
+}
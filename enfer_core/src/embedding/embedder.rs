@@ -0,0 +1,69 @@
+use crate::embedding::Embedding;
+use crate::embedding::cache::CachedSemantic;
+use crate::embedding::pool::SemanticPool;
+use crate::embedding::semantic::{Semantic, SemanticError};
+
+/// Turns text into embeddings, whether with a model or without one, so callers can be generic over where their
+/// embeddings come from. [`Semantic`], its [`SemanticPool`] and [`CachedSemantic`] wrappers and the model-free
+/// [`crate::HashEmbedder`] all implement it.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{Embedder, HashEmbedder, Semantic};
+///
+/// // keep working with keyword-level embeddings when the model can't be loaded
+/// let embedder: Box<dyn Embedder> = match Semantic::from_files("../model/model.onnx", "../model/tokenizer.json") {
+///     Ok(semantic) => Box::new(semantic),
+///     Err(_) => Box::new(HashEmbedder::new(384).unwrap()),
+/// };
+/// assert_eq!(embedder.embed("Hello world!").unwrap().len(), embedder.embedding_dim());
+/// ```
+pub trait Embedder: Send + Sync {
+    fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError>;
+
+    /// Embeds every sequence, in order. Embedders that can't run a batch at once embed the sequences one by one.
+    fn embed_batch(&self, sequences: &[&str]) -> Result<Vec<Embedding>, SemanticError> {
+        sequences.iter().map(|sequence| self.embed(sequence)).collect()
+    }
+
+    /// The number of dimensions of every embedding.
+    fn embedding_dim(&self) -> usize;
+}
+
+impl Embedder for Semantic {
+    fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        Semantic::embed(self, sequence)
+    }
+
+    fn embed_batch(&self, sequences: &[&str]) -> Result<Vec<Embedding>, SemanticError> {
+        Semantic::embed_batch(self, sequences)
+    }
+
+    fn embedding_dim(&self) -> usize {
+        Semantic::embedding_dim(self)
+    }
+}
+
+impl Embedder for SemanticPool {
+    fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        SemanticPool::embed(self, sequence)
+    }
+
+    fn embed_batch(&self, sequences: &[&str]) -> Result<Vec<Embedding>, SemanticError> {
+        SemanticPool::embed_batch(self, sequences)
+    }
+
+    fn embedding_dim(&self) -> usize {
+        SemanticPool::embedding_dim(self)
+    }
+}
+
+impl Embedder for CachedSemantic {
+    fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        CachedSemantic::embed(self, sequence)
+    }
+
+    fn embedding_dim(&self) -> usize {
+        self.semantic().embedding_dim()
+    }
+}
@@ -0,0 +1,51 @@
+use js_sys::{Promise, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+use crate::embedding::semantic::{Semantic, SemanticError};
+
+#[wasm_bindgen]
+extern "C" {
+    /// The global `fetch`, so it works in a window as well as in a web worker.
+    #[wasm_bindgen(js_name = fetch)]
+    fn global_fetch(url: &str) -> Promise;
+}
+
+impl Semantic {
+    /// Downloads the model and the `tokenizer.json` with the browser's fetch API, then initializes a [`Semantic`]
+    /// with the default options. A response other than 2xx fails with [`SemanticError::FetchFailed`] and its status,
+    /// a network failure, such as a blocked cross-origin request, without one. Needs the `wasm` feature and only works
+    /// on `wasm32-unknown-unknown` in a JavaScript host that has `fetch`.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::Semantic;
+    ///
+    /// let semantic = Semantic::from_url("/assets/model.onnx", "/assets/tokenizer.json").await.unwrap();
+    /// let embedding = semantic.embed("Hello world!").unwrap();
+    /// ```
+    pub async fn from_url(model_url: &str, tokenizer_url: &str) -> Result<Semantic, SemanticError> {
+        let model = fetch_bytes(model_url).await?;
+        let tokenizer_data = fetch_bytes(tokenizer_url).await?;
+
+        Self::init_semantic(model, tokenizer_data)
+    }
+}
+
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, SemanticError> {
+    let failed = |status: Option<u16>| SemanticError::FetchFailed { url: url.to_string(), status };
+
+    let response = JsFuture::from(global_fetch(url)).await
+        .and_then(|response| response.dyn_into::<Response>())
+        .map_err(|_| failed(None))?;
+    if !response.ok() {
+        return Err(failed(Some(response.status())));
+    }
+
+    let body = response.array_buffer().map_err(|_| failed(None))?;
+    let buffer = JsFuture::from(body).await.map_err(|_| failed(None))?;
+
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
@@ -0,0 +1,137 @@
+use crate::embedding::Embedding;
+use crate::embedding::embedder::Embedder;
+use crate::embedding::math::normalize_in_place;
+use crate::embedding::semantic::SemanticError;
+
+/// Embeds text without a model, with the hashing trick: every lowercased word adds `1` or `-1`, picked by its hash,
+/// to the dimension its hash lands on, and the sum is L2-normalized. Texts sharing words end up close, but synonyms
+/// and paraphrases don't, so it is no substitute for a model. The words are hashed with FNV-1a, so the embeddings
+/// are the same across runs, platforms and releases, which makes it a fallback for when the model can't be loaded
+/// in the browser, and a stand-in for the model in tests.
+///
+/// Example:
+/// ```rust
+/// use inference_core::HashEmbedder;
+///
+/// let embedder = HashEmbedder::new(256).unwrap();
+/// let query = embedder.embed("Where is the train station?").unwrap();
+/// let close = embedder.embed("The train station is next to the park").unwrap();
+/// let far = embedder.embed("Recipes for chocolate cake").unwrap();
+/// assert!(query.cosine_similarity(&close).unwrap() > query.cosine_similarity(&far).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashEmbedder {
+    dim: usize,
+}
+
+impl HashEmbedder {
+    /// Hashes words into `dim` dimensions, fails with [`SemanticError::InvalidHashDimension`] when it's `0`. Fewer
+    /// dimensions make more words collide, a few hundred keep collisions rare for short texts.
+    pub fn new(dim: usize) -> Result<HashEmbedder, SemanticError> {
+        if dim == 0 {
+            return Err(SemanticError::InvalidHashDimension(dim));
+        }
+
+        Ok(HashEmbedder { dim })
+    }
+
+    pub fn embedding_dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Words are the runs of alphanumeric characters, so punctuation and case don't change the embedding. Empty or
+    /// whitespace-only text fails with [`SemanticError::EmptyInput`], and text without any word, such as `"?!"`, with
+    /// [`SemanticError::EmptyTokenization`], like [`crate::Semantic::embed`] does.
+    pub fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        if sequence.trim().is_empty() {
+            return Err(SemanticError::EmptyInput);
+        }
+
+        let mut values = vec![0.0; self.dim];
+        let mut words = 0;
+        for word in sequence.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+            let hash = fnv1a(word.to_lowercase().as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            values[(hash % self.dim as u64) as usize] += sign;
+            words += 1;
+        }
+        if words == 0 {
+            return Err(SemanticError::EmptyTokenization);
+        }
+        normalize_in_place(&mut values);
+
+        Ok(Embedding(values))
+    }
+}
+
+impl Embedder for HashEmbedder {
+    fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        HashEmbedder::embed(self, sequence)
+    }
+
+    fn embedding_dim(&self) -> usize {
+        self.dim
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike std's hashers is specified and so never changes.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_hash_with_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn should_embed_deterministically_ignoring_case_and_punctuation() {
+        let embedder = HashEmbedder::new(64).unwrap();
+
+        let embedding = embedder.embed("Hello world").unwrap();
+
+        assert_eq!(embedding.len(), 64);
+        assert!((embedding.norm() - 1.0).abs() < 1e-6);
+        assert_eq!(embedding, embedder.embed("hello, WORLD!").unwrap());
+        assert_eq!(embedding, HashEmbedder::new(64).unwrap().embed("Hello world").unwrap());
+        assert_ne!(embedding, embedder.embed("Hello there").unwrap());
+    }
+
+    #[test]
+    fn should_place_each_word_in_its_hashed_dimension() {
+        let embedder = HashEmbedder::new(8).unwrap();
+        let hash = fnv1a(b"hello");
+
+        let embedding = embedder.embed("hello").unwrap();
+
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        let mut expected = vec![0.0; 8];
+        expected[(hash % 8) as usize] = sign;
+        assert_eq!(embedding.into_vec(), expected);
+    }
+
+    #[test]
+    fn should_reject_invalid_input() {
+        assert!(matches!(HashEmbedder::new(0), Err(SemanticError::InvalidHashDimension(0))));
+
+        let embedder = HashEmbedder::new(16).unwrap();
+        assert!(matches!(embedder.embed("  "), Err(SemanticError::EmptyInput)));
+        assert!(matches!(embedder.embed("?!"), Err(SemanticError::EmptyTokenization)));
+    }
+
+    #[test]
+    fn should_embed_batch_through_trait_object() {
+        let embedder: Box<dyn Embedder> = Box::new(HashEmbedder::new(32).unwrap());
+
+        let embeddings = embedder.embed_batch(&["first text", "second text"]).unwrap();
+
+        assert_eq!(embedder.embedding_dim(), 32);
+        assert_eq!(embeddings, vec![embedder.embed("first text").unwrap(), embedder.embed("second text").unwrap()]);
+    }
+}
@@ -0,0 +1,62 @@
+/// What a model declares about itself, as read by [`crate::Semantic::model_info`]. Metadata fields the model
+/// doesn't carry, or that ORT fails to read, are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// The ONNX graph name.
+    pub graph_name: Option<String>,
+    /// The tool that exported the model, e.g. `pytorch`.
+    pub producer: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<i64>,
+    pub inputs: Vec<TensorSpec>,
+    pub outputs: Vec<TensorSpec>,
+}
+
+/// A model input or output. Dynamic axes, such as the batch size or sequence length, have a size of `-1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TensorSpec {
+    pub name: String,
+    /// The element type, e.g. `Float32` or `Int64`, or the kind of value when it isn't a tensor.
+    pub dtype: String,
+    pub shape: Vec<i64>,
+}
+
+impl TensorSpec {
+    pub(crate) fn new(name: &str, value_type: &ort::ValueType) -> Self {
+        let (dtype, shape) = match value_type {
+            ort::ValueType::Tensor { ty, dimensions } => (format!("{:?}", ty), dimensions.clone()),
+            other => (format!("{:?}", other), vec![]),
+        };
+
+        TensorSpec { name: name.to_string(), dtype, shape }
+    }
+}
+
+impl ModelInfo {
+    pub(crate) fn read(session: &ort::Session) -> Self {
+        let metadata = session.metadata().ok();
+
+        ModelInfo {
+            graph_name: metadata.as_ref().and_then(|metadata| metadata.name().ok()),
+            producer: metadata.as_ref().and_then(|metadata| metadata.producer().ok()),
+            description: metadata.as_ref().and_then(|metadata| metadata.description().ok()),
+            version: metadata.as_ref().and_then(|metadata| metadata.version().ok()),
+            inputs: session.inputs.iter().map(|input| TensorSpec::new(&input.name, &input.input_type)).collect(),
+            outputs: session.outputs.iter().map(|output| TensorSpec::new(&output.name, &output.output_type)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_describe_tensor() {
+        let value_type = ort::ValueType::Tensor { ty: ort::TensorElementDataType::Int64, dimensions: vec![-1, -1] };
+
+        let spec = TensorSpec::new("input_ids", &value_type);
+
+        assert_eq!(spec, TensorSpec { name: "input_ids".to_string(), dtype: "Int64".to_string(), shape: vec![-1, -1] });
+    }
+}
@@ -0,0 +1,125 @@
+use crate::embedding::semantic::SemanticError;
+
+/// The model input names each tensor is bound to. The defaults follow the Hugging Face export conventions,
+/// override them for models exported with other names (e.g. `input.1`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputNames {
+    pub input_ids: String,
+    pub attention_mask: String,
+    pub token_type_ids: String,
+}
+
+impl Default for InputNames {
+    fn default() -> Self {
+        InputNames {
+            input_ids: "input_ids".to_string(),
+            attention_mask: "attention_mask".to_string(),
+            token_type_ids: "token_type_ids".to_string(),
+        }
+    }
+}
+
+/// The inputs a model declares, resolved once when the session is created. Tensors are bound by name,
+/// so the order the model was exported with doesn't matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModelInputs {
+    pub input_ids: String,
+    pub attention_mask: Option<String>,
+    /// BERT-style models take `token_type_ids`, while many newer ones (E5, GTE) only take `input_ids`
+    /// and `attention_mask` and reject any extra input.
+    pub token_type_ids: Option<String>,
+    /// The sequence length, when the model declares a fixed one instead of using a dynamic axis.
+    pub declared_length: Option<usize>,
+}
+
+impl ModelInputs {
+    pub fn resolve(declared: &[ort::Input], names: &InputNames) -> Result<Self, SemanticError> {
+        let find = |name: &str| declared.iter().find(|input| input.name == name);
+
+        let input_ids = find(&names.input_ids)
+            .ok_or_else(|| SemanticError::MissingModelInput(names.input_ids.clone()))?;
+        let declared_length = match &input_ids.input_type {
+            ort::ValueType::Tensor { dimensions, .. } => dimensions.get(1)
+                .filter(|length| **length > 0)
+                .map(|length| *length as usize),
+            _ => None,
+        };
+
+        Ok(ModelInputs {
+            input_ids: input_ids.name.clone(),
+            attention_mask: find(&names.attention_mask).map(|input| input.name.clone()),
+            token_type_ids: find(&names.token_type_ids).map(|input| input.name.clone()),
+            declared_length,
+        })
+    }
+
+    /// Pairs each tensor with the input name it is bound to, leaving out the ones the model doesn't declare.
+    pub fn bind(&self, input_ids: ort::Value, attention_mask: ort::Value, token_type_ids: ort::Value) -> Vec<(&str, ort::Value)> {
+        let mut inputs = vec![(self.input_ids.as_str(), input_ids)];
+        if let Some(name) = &self.attention_mask {
+            inputs.push((name.as_str(), attention_mask));
+        }
+        if let Some(name) = &self.token_type_ids {
+            inputs.push((name.as_str(), token_type_ids));
+        }
+
+        inputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ort::{Input, TensorElementDataType, ValueType};
+
+    use super::*;
+
+    fn input(name: &str) -> Input {
+        sized_input(name, vec![-1, -1])
+    }
+
+    fn sized_input(name: &str, dimensions: Vec<i64>) -> Input {
+        Input {
+            name: name.to_string(),
+            input_type: ValueType::Tensor { ty: TensorElementDataType::Int64, dimensions },
+        }
+    }
+
+    #[test]
+    fn should_detect_token_type_ids() {
+        let declared = [input("token_type_ids"), input("input_ids"), input("attention_mask")];
+
+        let inputs = ModelInputs::resolve(&declared, &InputNames::default()).unwrap();
+
+        assert_eq!(inputs.token_type_ids.as_deref(), Some("token_type_ids"));
+    }
+
+    #[test]
+    fn should_skip_token_type_ids_for_two_input_models() {
+        let declared = [input("input_ids"), input("attention_mask")];
+
+        let inputs = ModelInputs::resolve(&declared, &InputNames::default()).unwrap();
+
+        assert_eq!(inputs.token_type_ids, None);
+    }
+
+    #[test]
+    fn should_read_declared_sequence_length() {
+        let fixed = [sized_input("input_ids", vec![1, 256])];
+        let dynamic = [input("input_ids")];
+
+        assert_eq!(ModelInputs::resolve(&fixed, &InputNames::default()).unwrap().declared_length, Some(256));
+        assert_eq!(ModelInputs::resolve(&dynamic, &InputNames::default()).unwrap().declared_length, None);
+    }
+
+    #[test]
+    fn should_resolve_custom_input_names() {
+        let declared = [input("input.1"), input("attention_mask")];
+        let names = InputNames { input_ids: "input.1".to_string(), ..Default::default() };
+
+        let inputs = ModelInputs::resolve(&declared, &names).unwrap();
+        assert_eq!(inputs.input_ids, "input.1");
+
+        let missing = ModelInputs::resolve(&declared, &InputNames::default());
+        assert!(matches!(missing, Err(SemanticError::MissingModelInput(name)) if name == "input_ids"));
+    }
+}
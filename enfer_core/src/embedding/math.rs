@@ -0,0 +1,126 @@
+//! The vector math behind [`crate::Embedding`], on plain slices. It only uses `core`, `alloc` and `libm`, so it also
+//! builds for `no_std` targets, see `no_std_check/`. Functions taking two slices expect them to have the same length,
+//! [`crate::Embedding`] checks it before calling them.
+//!
+//! With the `simd` feature the dot products run on `core::simd` lanes, otherwise, and for the tail of vectors that
+//! aren't a multiple of the lane count, on a scalar loop. `benches/cosine.rs` times both on 768-dimensional vectors.
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "simd")]
+use core::simd::{f32x8, num::SimdFloat};
+
+#[cfg(feature = "simd")]
+const LANES: usize = 8;
+
+/// The dot product of two slices of the same length.
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len());
+
+    #[cfg(feature = "simd")]
+    {
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let tail = scalar_dot(a_chunks.remainder(), b_chunks.remainder());
+        let lanes = a_chunks.zip(b_chunks)
+            .fold(f32x8::splat(0.0), |sum, (a, b)| sum + f32x8::from_slice(a) * f32x8::from_slice(b));
+
+        lanes.reduce_sum() + tail
+    }
+
+    #[cfg(not(feature = "simd"))]
+    scalar_dot(a, b)
+}
+
+fn scalar_dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// The L2 (euclidean) norm.
+pub(crate) fn norm(values: &[f32]) -> f32 {
+    libm::sqrtf(dot(values, values))
+}
+
+/// Scales the values to unit length without allocating, a zero vector is left untouched.
+pub(crate) fn normalize_in_place(values: &mut [f32]) {
+    let norm = norm(values);
+    if norm == 0.0 {
+        return;
+    }
+
+    values.iter_mut().for_each(|x| *x /= norm);
+}
+
+/// The cosine of the angle between two vectors, `0.0` when either has a zero norm.
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    cosine_with_norm(a, norm(a), b)
+}
+
+/// Like [`cosine_similarity`] with the norm of `a` already known, for scoring one query against many vectors.
+pub(crate) fn cosine_with_norm(a: &[f32], a_norm: f32, b: &[f32]) -> f32 {
+    let norms = a_norm * norm(b);
+    if norms == 0.0 {
+        return 0.0;
+    }
+
+    dot(a, b) / norms
+}
+
+pub(crate) fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
+    libm::sqrtf(a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum())
+}
+
+pub(crate) fn manhattan_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| libm::fabsf(a - b)).sum()
+}
+
+/// Whether every pair of values differs by at most `epsilon`, NaNs never do.
+pub(crate) fn approx_eq(a: &[f32], b: &[f32], epsilon: f32) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| libm::fabsf(a - b) <= epsilon)
+}
+
+/// Quantizes to `i8` with one symmetric scale mapping the largest absolute value to `127`, see
+/// [`crate::Embedding::quantize_int8`].
+pub(crate) fn quantize_int8(values: &[f32]) -> (Vec<i8>, f32) {
+    let max_abs = values.iter().fold(0.0f32, |max, value| max.max(libm::fabsf(*value)));
+    if max_abs == 0.0 {
+        return (alloc::vec![0; values.len()], 0.0);
+    }
+
+    let scale = max_abs / 127.0;
+    let quantized = values.iter()
+        .map(|value| libm::roundf(value / scale).clamp(-127.0, 127.0) as i8)
+        .collect();
+
+    (quantized, scale)
+}
+
+pub(crate) fn dequantize_int8(quantized: &[i8], scale: f32) -> Vec<f32> {
+    quantized.iter().map(|q| *q as f32 * scale).collect()
+}
+
+/// Packs the signs 8 dimensions per byte, see [`crate::Embedding::to_binary`].
+pub(crate) fn to_binary(values: &[f32]) -> Vec<u8> {
+    values.chunks(8)
+        .map(|chunk| chunk.iter()
+            .enumerate()
+            .filter(|(_, value)| **value > 0.0)
+            .fold(0u8, |byte, (bit, _)| byte | (0x80 >> bit)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_scalar_dot_product() {
+        for length in [0, 1, 7, 8, 9, 384, 768, 1023] {
+            let a = (0..length).map(|i| ((i * 37 % 101) as f32 - 50.0) / 50.0).collect::<Vec<_>>();
+            let b = (0..length).map(|i| ((i * 53 % 97) as f32 - 48.0) / 48.0).collect::<Vec<_>>();
+            let expected = a.iter().zip(&b).map(|(a, b)| *a as f64 * *b as f64).sum::<f64>();
+
+            assert!((dot(&a, &b) as f64 - expected).abs() < 1e-3, "dot product of {} values", length);
+            assert!((norm(&a) - scalar_dot(&a, &a).sqrt()).abs() < 1e-3);
+        }
+    }
+}
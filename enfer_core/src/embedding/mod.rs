@@ -1,20 +1,260 @@
+#[cfg(feature = "bundle")]
+pub use bundle::{BUNDLE_MODEL_FILE, BUNDLE_TOKENIZER_FILE};
+pub use builder::SemanticBuilder;
+pub use cache::CachedSemantic;
+pub use config::{EmbedOptions, LogLevel, OptimizationLevel, PaddingSide, Precision, SemanticConfig};
+pub use embedder::Embedder;
+pub use hash::HashEmbedder;
+pub use info::{ModelInfo, TensorSpec};
+pub use inputs::InputNames;
+#[cfg(feature = "npy")]
+pub use npy::{read_npy, write_npy};
+pub use outputs::{ModelKind, OutputSelector};
+pub use pool::SemanticPool;
+pub use pooling::{mean_pooling, PoolingScope, PoolingStrategy};
+pub use provider::{ExecutionProvider, ExecutionProviderKind};
+pub use semantic::EmbedOutput;
 pub use semantic::Semantic;
+pub use semantic::SemanticError;
+pub use stats::{LengthStats, ProcessMemory};
+pub use tokenizer::{TextNormalizer, TokenizerFormat};
+
+use std::fmt;
 
 use crate::UniffiCustomTypeConverter;
 
+pub(crate) mod batching;
+pub(crate) mod buffers;
+#[cfg(feature = "bundle")]
+pub(crate) mod bundle;
+pub(crate) mod builder;
+pub(crate) mod cache;
+pub(crate) mod config;
+pub(crate) mod embedder;
+#[cfg(feature = "wasm")]
+pub(crate) mod fetch;
+pub(crate) mod hash;
+pub(crate) mod info;
+pub(crate) mod inputs;
+pub(crate) mod math;
+#[cfg(feature = "npy")]
+pub(crate) mod npy;
+pub(crate) mod outputs;
+pub(crate) mod pool;
+pub(crate) mod pooling;
+pub(crate) mod protobuf;
+pub(crate) mod provider;
 pub(crate) mod semantic;
+pub(crate) mod sentences;
+pub(crate) mod stats;
+#[cfg(feature = "serde")]
+pub(crate) mod template;
+pub(crate) mod tokenizer;
+pub(crate) mod validation;
 
-#[derive(Debug, Clone)]
+/// A dense vector produced by a [`Semantic`]. Prefer [`Embedding::as_slice`] and [`Embedding::into_vec`] over
+/// the tuple field, which may become private once the storage changes.
+///
+/// `==` compares the values exactly, which rarely holds for embeddings computed on different hardware or batch
+/// sizes, prefer [`Embedding::approx_eq`].
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Embedding(pub Vec<f32>);
 
 impl Embedding {
+    /// Wraps values loaded from elsewhere, such as a cache or a database, failing with
+    /// [`SemanticError::InvalidEmbedding`] when the vector is empty or contains NaN or infinite values.
+    /// [`Embedding::from`] takes the values as they are.
+    pub fn try_new(values: Vec<f32>) -> Result<Embedding, SemanticError> {
+        if values.is_empty() {
+            return Err(SemanticError::InvalidEmbedding("the vector is empty".to_string()));
+        }
+        if let Some(index) = values.iter().position(|value| !value.is_finite()) {
+            return Err(SemanticError::InvalidEmbedding(format!("{} at index {}", values[index], index)));
+        }
+
+        Ok(Embedding(values))
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[f32] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<f32> {
+        self.0
+    }
+
     pub fn iter(&self) -> std::slice::Iter<'_, f32> {
         self.0.iter()
     }
+
+    /// The L2 (euclidean) norm of the vector.
+    pub fn norm(&self) -> f32 {
+        math::norm(&self.0)
+    }
+
+    /// Returns a copy scaled to unit length, so the cosine similarity of two normalized embeddings is their dot product.
+    /// A zero vector has no direction and is returned unchanged instead of turning into NaNs.
+    pub fn normalized(&self) -> Embedding {
+        let mut values = self.0.clone();
+        math::normalize_in_place(&mut values);
+
+        Embedding(values)
+    }
+
+    /// The dot product of two embeddings, fails when they have a different number of dimensions.
+    pub fn dot(&self, other: &Embedding) -> Result<f32, SemanticError> {
+        self.ensure_same_dim(other)?;
+
+        Ok(math::dot(&self.0, &other.0))
+    }
+
+    /// The cosine similarity of two embeddings, in `[-1, 1]`. Fails when they have a different number of dimensions.
+    /// If either vector has a zero norm the angle is undefined and the similarity is `0.0`.
+    pub fn cosine_similarity(&self, other: &Embedding) -> Result<f32, SemanticError> {
+        self.ensure_same_dim(other)?;
+
+        Ok(math::cosine_similarity(&self.0, &other.0))
+    }
+
+    /// The cosine similarity of this embedding, typically a query, with each of the candidates, in the order of
+    /// `candidates`. The query's norm is computed once for all of them. Candidates with a zero norm get a similarity
+    /// of `0.0`, and any candidate with a different number of dimensions fails the whole call.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::Embedding;
+    ///
+    /// let query = Embedding(vec![1.0, 0.0]);
+    /// let similarities = query.similarities(&[Embedding(vec![0.0, 1.0]), Embedding(vec![2.0, 0.0])]).unwrap();
+    /// assert_eq!(similarities, vec![0.0, 1.0]);
+    /// ```
+    pub fn similarities(&self, candidates: &[Embedding]) -> Result<Vec<f32>, SemanticError> {
+        if let Some(candidate) = candidates.iter().find(|candidate| candidate.len() != self.len()) {
+            return Err(SemanticError::DimensionMismatch { expected: self.len(), actual: candidate.len() });
+        }
+
+        let query_norm = self.norm();
+        Ok(candidates.iter().map(|candidate| math::cosine_with_norm(&self.0, query_norm, &candidate.0)).collect())
+    }
+
+    /// The straight-line (L2) distance between two embeddings, fails when they have a different number of dimensions.
+    pub fn euclidean_distance(&self, other: &Embedding) -> Result<f32, SemanticError> {
+        self.ensure_same_dim(other)?;
+
+        Ok(math::euclidean_distance(&self.0, &other.0))
+    }
+
+    /// The sum of absolute differences (L1) between two embeddings, fails when they have a different number of dimensions.
+    pub fn manhattan_distance(&self, other: &Embedding) -> Result<f32, SemanticError> {
+        self.ensure_same_dim(other)?;
+
+        Ok(math::manhattan_distance(&self.0, &other.0))
+    }
+
+    /// Whether both embeddings have the same number of dimensions and every pair of values differs by at most
+    /// `epsilon`. A NaN is never approximately equal to anything.
+    pub fn approx_eq(&self, other: &Embedding, epsilon: f32) -> bool {
+        math::approx_eq(&self.0, &other.0, epsilon)
+    }
+
+    /// Keeps the first `dim` dimensions, for embeddings of Matryoshka models that stay meaningful when truncated.
+    /// The truncated vector is no longer unit length, call [`Embedding::normalized`] to compare it by dot product.
+    /// Fails with [`SemanticError::InvalidDimension`] when `dim` is `0` or longer than the embedding.
+    pub fn truncate_dim(&self, dim: usize) -> Result<Embedding, SemanticError> {
+        if dim == 0 || dim > self.len() {
+            return Err(SemanticError::InvalidDimension { dim, available: self.len() });
+        }
+
+        Ok(Embedding(self.0[..dim].to_vec()))
+    }
+
+    /// Quantizes the vector to `i8` with one symmetric scale, so `value ≈ q * scale`, for a 4x smaller store.
+    /// The scale maps the largest absolute value to `127`, a zero vector has a scale of `0.0`.
+    pub fn quantize_int8(&self) -> (Vec<i8>, f32) {
+        math::quantize_int8(&self.0)
+    }
+
+    /// Restores an approximation of a vector quantized with [`Embedding::quantize_int8`].
+    pub fn dequantize_int8(quantized: &[i8], scale: f32) -> Embedding {
+        Embedding(math::dequantize_int8(quantized, scale))
+    }
+
+    /// Keeps only the sign of every dimension, packed 8 dimensions per byte with the first one in the highest bit.
+    /// A set bit means a positive value, and the last byte is padded with zeros.
+    ///
+    /// Binary embeddings are 32x smaller and compared with [`crate::hamming_distance`], which is much cheaper than
+    /// cosine similarity, but they lose most of the magnitude information and recall drops accordingly. Use them to
+    /// prefilter candidates, then rerank the survivors with the full vectors.
+    pub fn to_binary(&self) -> Vec<u8> {
+        math::to_binary(&self.0)
+    }
+
+    /// Packs the vector as little-endian `f32`s, 4 bytes per dimension, for compact blob storage.
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    /// Unpacks a vector written by [`Embedding::to_le_bytes`], fails if the length isn't a multiple of 4.
+    pub fn from_le_bytes(bytes: &[u8]) -> Result<Embedding, SemanticError> {
+        if bytes.len() % 4 != 0 {
+            return Err(SemanticError::InvalidByteLength(bytes.len()));
+        }
+
+        Ok(Embedding(bytes.chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()))
+    }
+
+    fn ensure_same_dim(&self, other: &Embedding) -> Result<(), SemanticError> {
+        if self.len() != other.len() {
+            return Err(SemanticError::DimensionMismatch { expected: self.len(), actual: other.len() });
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Vec<f32>> for Embedding {
+    fn from(values: Vec<f32>) -> Self {
+        Embedding(values)
+    }
+}
+
+/// Validates the values like [`Embedding::try_new`]. `TryFrom<Vec<f32>>` comes with the [`From`] impl and never fails.
+impl TryFrom<&[f32]> for Embedding {
+    type Error = SemanticError;
+
+    fn try_from(values: &[f32]) -> Result<Self, Self::Error> {
+        Embedding::try_new(values.to_vec())
+    }
+}
+
+/// Summarizes the vector instead of printing every value, e.g. `Embedding(dim=384, norm=1.00, [0.01, -0.23, 0.05, ...])`.
+impl fmt::Debug for Embedding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const PREVIEW: usize = 3;
+
+        write!(f, "Embedding(dim={}, norm={:.2}, [", self.len(), self.norm())?;
+        for (index, value) in self.0.iter().take(PREVIEW).enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:.2}", value)?;
+        }
+        if self.len() > PREVIEW {
+            write!(f, ", ...")?;
+        }
+        write!(f, "])")
+    }
 }
 
 impl UniffiCustomTypeConverter for Embedding {
@@ -28,4 +268,168 @@ impl UniffiCustomTypeConverter for Embedding {
     fn from_custom(obj: Self) -> Self::Builtin {
         obj.0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_validate_values() {
+        let values = [0.1, f32::NAN, 0.3];
+
+        assert!(matches!(Embedding::try_from(&values[..]), Err(SemanticError::InvalidEmbedding(_))));
+        assert!(matches!(Embedding::try_new(vec![f32::INFINITY]), Err(SemanticError::InvalidEmbedding(_))));
+        assert!(matches!(Embedding::try_new(vec![]), Err(SemanticError::InvalidEmbedding(_))));
+        assert_eq!(Embedding::try_from(&[0.1, 0.2][..]).unwrap().0, vec![0.1, 0.2]);
+        assert_eq!(Embedding::from(vec![f32::NAN]).len(), 1);
+    }
+
+    #[test]
+    fn should_normalize_to_unit_length() {
+        let embedding = Embedding(vec![3.0, 4.0, 12.0]);
+
+        let normalized = embedding.normalized();
+
+        assert!((normalized.norm() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn should_not_produce_nan_for_zero_vector() {
+        let embedding = Embedding(vec![0.0; 4]);
+
+        let normalized = embedding.normalized();
+
+        assert!(normalized.iter().all(|x| !x.is_nan()));
+        assert_eq!(normalized.0, vec![0.0; 4]);
+    }
+
+    #[test]
+    fn should_compute_dot_and_cosine_similarity() {
+        let a = Embedding(vec![1.0, 2.0, 3.0]);
+        let b = Embedding(vec![2.0, 4.0, 6.0]);
+
+        assert_eq!(a.dot(&b).unwrap(), 28.0);
+        assert!((a.cosine_similarity(&b).unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(a.cosine_similarity(&Embedding(vec![0.0; 3])).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn should_rank_candidates_by_similarity() {
+        let query = Embedding(vec![1.0, 1.0, 0.0]);
+        let candidates = [Embedding(vec![0.0, 0.0, 1.0]), Embedding(vec![2.0, 2.1, 0.0]), Embedding(vec![1.0, 0.0, 0.0]), Embedding(vec![0.0; 3])];
+
+        let similarities = query.similarities(&candidates).unwrap();
+
+        for (similarity, candidate) in similarities.iter().zip(&candidates) {
+            assert!((similarity - query.cosine_similarity(candidate).unwrap()).abs() < 1e-6);
+        }
+        let best = similarities.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+        assert_eq!(best, 1);
+        assert!(query.similarities(&[]).unwrap().is_empty());
+        assert!(matches!(query.similarities(&[Embedding(vec![1.0, 0.0])]), Err(SemanticError::DimensionMismatch { .. })));
+    }
+
+    #[test]
+    fn should_compute_euclidean_and_manhattan_distance() {
+        let a = Embedding(vec![1.0, 2.0, 3.0]);
+        let b = Embedding(vec![4.0, 6.0, 3.0]);
+
+        assert_eq!(a.euclidean_distance(&b).unwrap(), 5.0);
+        assert_eq!(a.manhattan_distance(&b).unwrap(), 7.0);
+        assert_eq!(a.euclidean_distance(&a).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn should_reject_dimension_mismatch() {
+        let a = Embedding(vec![1.0, 2.0, 3.0]);
+        let b = Embedding(vec![1.0, 2.0]);
+
+        assert!(matches!(a.dot(&b), Err(SemanticError::DimensionMismatch { expected: 3, actual: 2 })));
+        assert!(a.cosine_similarity(&b).is_err());
+        assert!(a.euclidean_distance(&b).is_err());
+        assert!(a.manhattan_distance(&b).is_err());
+    }
+
+    #[test]
+    fn should_truncate_to_leading_dimensions() {
+        let embedding = Embedding(vec![3.0, 4.0, 12.0]).normalized();
+
+        let truncated = embedding.truncate_dim(2).unwrap();
+
+        assert_eq!(truncated.len(), 2);
+        assert!((truncated.normalized().norm() - 1.0).abs() < 1e-6);
+        assert!(truncated.normalized().approx_eq(&Embedding(vec![0.6, 0.8]), 1e-6));
+        assert!(matches!(embedding.truncate_dim(4), Err(SemanticError::InvalidDimension { dim: 4, available: 3 })));
+        assert!(matches!(embedding.truncate_dim(0), Err(SemanticError::InvalidDimension { .. })));
+    }
+
+    #[test]
+    fn should_compare_within_tolerance() {
+        let a = Embedding(vec![0.1, 0.2, 0.3]);
+
+        assert!(a.approx_eq(&Embedding(vec![0.1, 0.2001, 0.2999]), 1e-3));
+        assert!(!a.approx_eq(&Embedding(vec![0.1, 0.21, 0.3]), 1e-3));
+        assert!(!a.approx_eq(&Embedding(vec![0.1, 0.2]), 1e-3));
+        assert!(!Embedding(vec![f32::NAN]).approx_eq(&Embedding(vec![f32::NAN]), 1e-3));
+        assert_eq!(a, a.clone());
+    }
+
+    #[test]
+    fn should_expose_values_through_accessors() {
+        let embedding = Embedding(vec![1.0, 2.0]);
+
+        assert_eq!(embedding.as_slice(), &[1.0, 2.0]);
+        assert!(!embedding.is_empty());
+        assert!(Embedding(vec![]).is_empty());
+        assert_eq!(embedding.into_vec(), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn should_summarize_in_debug_output() {
+        let embedding = Embedding(vec![0.6, -0.8, 0.0, 0.0]);
+
+        assert_eq!(format!("{:?}", embedding), "Embedding(dim=4, norm=1.00, [0.60, -0.80, 0.00, ...])");
+        assert_eq!(format!("{:?}", Embedding(vec![3.0, 4.0])), "Embedding(dim=2, norm=5.00, [3.00, 4.00])");
+    }
+
+    #[test]
+    fn should_round_trip_through_le_bytes() {
+        let embedding = Embedding((0..384).map(|i| (i as f32 * 12.9898).sin() * 43.758).collect());
+
+        let bytes = embedding.to_le_bytes();
+        assert_eq!(bytes.len(), 384 * 4);
+
+        let decoded = Embedding::from_le_bytes(&bytes).unwrap();
+        assert_eq!(decoded.0, embedding.0);
+    }
+
+    #[test]
+    fn should_keep_similarity_through_int8_quantization() {
+        let embedding = Embedding((0..384).map(|i| (i as f32 * 0.37).sin() * (i as f32 * 0.11).cos()).collect());
+
+        let (quantized, scale) = embedding.quantize_int8();
+        let restored = Embedding::dequantize_int8(&quantized, scale);
+
+        assert_eq!(quantized.len(), 384);
+        assert!(embedding.cosine_similarity(&restored).unwrap() > 0.99);
+        assert_eq!(Embedding(vec![0.0; 3]).quantize_int8(), (vec![0; 3], 0.0));
+    }
+
+    #[test]
+    fn should_reject_truncated_bytes() {
+        assert!(matches!(Embedding::from_le_bytes(&[0, 0, 128]), Err(SemanticError::InvalidByteLength(3))));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn should_round_trip_through_json() {
+        let embedding = Embedding(vec![0.25, -1.5, 3.0]);
+
+        let json = serde_json::to_string(&embedding).unwrap();
+        assert_eq!(json, "[0.25,-1.5,3.0]");
+
+        let decoded: Embedding = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.0, embedding.0);
+    }
+}
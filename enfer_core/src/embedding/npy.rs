@@ -0,0 +1,178 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::embedding::{Embedding, SemanticError};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+/// numpy aligns the data to 64 bytes, so the header is padded up to it.
+const ALIGNMENT: usize = 64;
+
+/// Writes the embeddings as one `(N, D)` little-endian `float32` array in numpy's `.npy` format, to be read with
+/// `numpy.load`. No embeddings give a `(0, 0)` array. Fails with [`SemanticError::DimensionMismatch`] when the
+/// embeddings don't all have the same number of dimensions, and with [`SemanticError::NpyIoError`] when the file
+/// can't be written. Needs the `npy` feature.
+///
+/// Example:
+/// ```rust,ignore
+/// use inference_core::{write_npy, Embedding};
+///
+/// write_npy("embeddings.npy", &[Embedding(vec![0.5, 1.0]), Embedding(vec![-1.0, 2.0])]).unwrap();
+/// // in Python: numpy.load("embeddings.npy").shape == (2, 2)
+/// ```
+pub fn write_npy(path: impl AsRef<Path>, embeddings: &[Embedding]) -> Result<(), SemanticError> {
+    let dim = embeddings.first().map_or(0, Embedding::len);
+    if let Some(embedding) = embeddings.iter().find(|embedding| embedding.len() != dim) {
+        return Err(SemanticError::DimensionMismatch { expected: dim, actual: embedding.len() });
+    }
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(SemanticError::NpyIoError)?);
+    file.write_all(&header(embeddings.len(), dim)).map_err(SemanticError::NpyIoError)?;
+    for value in embeddings.iter().flat_map(Embedding::iter) {
+        file.write_all(&value.to_le_bytes()).map_err(SemanticError::NpyIoError)?;
+    }
+
+    file.flush().map_err(SemanticError::NpyIoError)
+}
+
+/// Reads a 2D `float32` array written by [`write_npy`], or by `numpy.save` in C order, back into one embedding per
+/// row. Other element types, Fortran order and any other rank fail with [`SemanticError::InvalidNpy`].
+pub fn read_npy(path: impl AsRef<Path>) -> Result<Vec<Embedding>, SemanticError> {
+    let mut bytes = vec![];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .map_err(SemanticError::NpyIoError)?;
+
+    let (rows, dim, data) = parse(&bytes).map_err(|reason| SemanticError::InvalidNpy(reason.to_string()))?;
+    // the shape comes from the file, so a crafted one could overflow the expected length
+    let length = rows.checked_mul(dim).and_then(|values| values.checked_mul(4))
+        .ok_or_else(|| SemanticError::InvalidNpy(format!("the shape ({}, {}) is too large", rows, dim)))?;
+    if data.len() != length {
+        return Err(SemanticError::InvalidNpy(format!("expected {} values, found {} bytes", rows * dim, data.len())));
+    }
+    if dim == 0 {
+        return Ok(vec![Embedding(vec![]); rows]);
+    }
+
+    Ok(data.chunks_exact(dim * 4)
+        .map(|row| Embedding(row.chunks_exact(4).map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]])).collect()))
+        .collect())
+}
+
+/// The magic string, version 1.0 and the header dictionary, padded with spaces to the alignment.
+fn header(rows: usize, dim: usize) -> Vec<u8> {
+    let mut dictionary = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}", rows, dim);
+    let unpadded = MAGIC.len() + 4 + dictionary.len() + 1;
+    dictionary.push_str(&" ".repeat((ALIGNMENT - unpadded % ALIGNMENT) % ALIGNMENT));
+    dictionary.push('\n');
+
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&[1, 0]);
+    header.extend_from_slice(&(dictionary.len() as u16).to_le_bytes());
+    header.extend_from_slice(dictionary.as_bytes());
+    header
+}
+
+/// Splits the file into its shape and data, checking the header describes a C-order `(rows, dim)` `<f4` array.
+fn parse(bytes: &[u8]) -> Result<(usize, usize, &[u8]), &'static str> {
+    let rest = bytes.strip_prefix(MAGIC).ok_or("not an npy file")?;
+    let (length, rest) = match rest {
+        [1, _, a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+        [2 | 3, _, a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+        _ => return Err("unsupported npy version"),
+    };
+    if rest.len() < length {
+        return Err("truncated header");
+    }
+    let (dictionary, data) = rest.split_at(length);
+    let dictionary = std::str::from_utf8(dictionary).map_err(|_| "header isn't text")?;
+
+    if !dictionary.contains("'descr': '<f4'") {
+        return Err("only little-endian float32 arrays are supported");
+    }
+    if !dictionary.contains("'fortran_order': False") {
+        return Err("only C-order arrays are supported");
+    }
+    let shape = dictionary.split("'shape': (").nth(1)
+        .and_then(|shape| shape.split(')').next())
+        .ok_or("header has no shape")?
+        .split(',')
+        .map(str::trim)
+        .filter(|length| !length.is_empty())
+        .map(|length| length.parse::<usize>().map_err(|_| "invalid shape"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match shape[..] {
+        [rows, dim] => Ok((rows, dim, data)),
+        _ => Err("only 2D arrays are supported"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.npy", name, std::process::id()))
+    }
+
+    #[test]
+    fn should_write_numpy_header() {
+        let header = header(2, 3);
+
+        assert_eq!(header.len() % ALIGNMENT, 0);
+        assert_eq!(&header[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!(u16::from_le_bytes([header[8], header[9]]) as usize, header.len() - 10);
+        let dictionary = std::str::from_utf8(&header[10..]).unwrap();
+        assert!(dictionary.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"));
+        assert!(dictionary.ends_with(" \n"));
+    }
+
+    #[test]
+    fn should_round_trip_embeddings() {
+        let path = temp_path("should_round_trip_embeddings");
+        let embeddings = vec![Embedding(vec![0.5, -1.25, 3.0]), Embedding(vec![0.0, f32::MAX, -0.125])];
+
+        write_npy(&path, &embeddings).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let read = read_npy(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let data = header(2, 3).len();
+        assert_eq!(bytes.len(), data + 2 * 3 * 4);
+        assert_eq!(&bytes[data..data + 4], &0.5f32.to_le_bytes());
+        assert_eq!(read, embeddings);
+    }
+
+    #[test]
+    fn should_round_trip_zero_dimensional_embeddings() {
+        let path = temp_path("should_round_trip_zero_dimensional_embeddings");
+        let embeddings = vec![Embedding(vec![]); 3];
+
+        write_npy(&path, &embeddings).unwrap();
+        let read = read_npy(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, embeddings);
+    }
+
+    #[test]
+    fn should_reject_invalid_input() {
+        let path = temp_path("should_reject_invalid_input");
+
+        assert!(matches!(write_npy(&path, &[Embedding(vec![1.0]), Embedding(vec![1.0, 2.0])]),
+            Err(SemanticError::DimensionMismatch { expected: 1, actual: 2 })));
+
+        let float64 = String::from_utf8(header(1, 1)[6..].to_vec()).unwrap().replace("<f4", "<f8");
+        std::fs::write(&path, [MAGIC, float64.as_bytes(), &[0; 8]].concat()).unwrap();
+        assert!(matches!(read_npy(&path), Err(SemanticError::InvalidNpy(_))));
+
+        std::fs::write(&path, [header(2, 2), vec![0; 4]].concat()).unwrap();
+        assert!(matches!(read_npy(&path), Err(SemanticError::InvalidNpy(_))));
+
+        std::fs::write(&path, header(4611686018427387904, 8)).unwrap();
+        assert!(matches!(read_npy(&path), Err(SemanticError::InvalidNpy(_))));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(read_npy(&path), Err(SemanticError::NpyIoError(_))));
+    }
+}
@@ -0,0 +1,384 @@
+use ndarray::{Array2, ArrayView2, ArrayView3, ArrayViewD, Axis, Ix2, Ix3};
+use ort::TensorElementDataType;
+
+use crate::embedding::pooling::PoolingStrategy;
+use crate::embedding::semantic::SemanticError;
+
+/// Custom metadata keys holding the affine parameters of an `int8`/`uint8` output, `real = (q - zero_point) * scale`.
+pub(crate) const QUANTIZATION_SCALE_KEY: &str = "quantization_scale";
+pub(crate) const QUANTIZATION_ZERO_POINT_KEY: &str = "quantization_zero_point";
+
+/// The output names sentence-transformers and Hugging Face exports use for the per-token embeddings.
+const TOKEN_EMBEDDING_OUTPUTS: [&str; 2] = ["last_hidden_state", "token_embeddings"];
+
+/// Which model output is pooled into the embedding. Models often also emit a pooled `pooler_output`, which is
+/// returned as it is when selected, without applying the pooling strategy.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum OutputSelector {
+    /// The first output named `last_hidden_state` or `token_embeddings`, and the first output if there is neither.
+    #[default]
+    Auto,
+    Name(String),
+    Index(usize),
+}
+
+impl OutputSelector {
+    pub(crate) fn select<'a>(&self, declared: &'a [ort::Output]) -> Result<&'a ort::Output, SemanticError> {
+        let selected = match self {
+            OutputSelector::Auto => declared.iter()
+                .find(|output| TOKEN_EMBEDDING_OUTPUTS.contains(&output.name.as_str()))
+                .or_else(|| declared.first()),
+            OutputSelector::Name(name) => declared.iter().find(|output| &output.name == name),
+            OutputSelector::Index(index) => declared.get(*index),
+        };
+
+        selected.ok_or_else(|| SemanticError::MissingModelOutput(match self {
+            OutputSelector::Auto => "any output".to_string(),
+            OutputSelector::Name(name) => name.clone(),
+            OutputSelector::Index(index) => format!("#{}", index),
+        }))
+    }
+}
+
+/// Whether a model embeds sequences or scores pairs of them, as told by the shape of its selected output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelKind {
+    /// Embeds each sequence on its own, from per-token or already pooled embeddings.
+    BiEncoder,
+    /// Scores a `(query, document)` pair at once, from a single relevance logit per pair, like the `(batch, 1)`
+    /// `logits` output of rerankers such as `cross-encoder/ms-marco-MiniLM-L-6-v2`.
+    CrossEncoder,
+}
+
+/// The output the embedding is pooled from, resolved once when the session is created.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModelOutput {
+    pub name: String,
+    pub element: OutputElement,
+    /// The hidden size, when the model declares it instead of using a dynamic axis. Cross-encoders output 1 score.
+    pub declared_dim: Option<usize>,
+    pub kind: ModelKind,
+}
+
+impl ModelOutput {
+    pub fn resolve(output: &ort::Output, quantization: Option<(f32, i32)>) -> Result<Self, SemanticError> {
+        let (declared_dim, kind) = match &output.output_type {
+            ort::ValueType::Tensor { dimensions, .. } if is_score_shape(dimensions) => (Some(1), ModelKind::CrossEncoder),
+            ort::ValueType::Tensor { dimensions, .. } => {
+                let declared_dim = dimensions.last()
+                    .filter(|dim| **dim > 0)
+                    .map(|dim| *dim as usize);
+                (declared_dim, ModelKind::BiEncoder)
+            }
+            _ => (None, ModelKind::BiEncoder),
+        };
+
+        Ok(ModelOutput {
+            name: output.name.clone(),
+            element: OutputElement::resolve(output, quantization)?,
+            declared_dim,
+            kind,
+        })
+    }
+}
+
+/// One scalar per sequence, either `(batch,)` or `(batch, 1)`, rather than embeddings with a hidden axis.
+fn is_score_shape(dimensions: &[i64]) -> bool {
+    matches!(dimensions, [_] | [_, 1])
+}
+
+/// The relevance score of the single pair a cross-encoder was run on.
+pub(crate) fn pair_score(output: ArrayViewD<f32>) -> Result<f32, SemanticError> {
+    if output.ndim() > 2 {
+        return Err(SemanticError::UnexpectedOutputRank(output.ndim()));
+    }
+
+    match (output.len(), output.iter().next()) {
+        (1, Some(score)) => Ok(*score),
+        _ => Err(incompatible_shape()),
+    }
+}
+
+/// How the elements of the model's output tensor are turned into `f32`s, resolved once when the session is created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum OutputElement {
+    Float32,
+    Float16,
+    Int8 { scale: f32, zero_point: i32 },
+    Uint8 { scale: f32, zero_point: i32 },
+}
+
+impl OutputElement {
+    /// Quantized outputs can only be dequantized when the model carries its scale and zero point.
+    pub fn resolve(output: &ort::Output, quantization: Option<(f32, i32)>) -> Result<Self, SemanticError> {
+        let ty = match &output.output_type {
+            ort::ValueType::Tensor { ty, .. } => *ty,
+            other => return Err(SemanticError::UnsupportedOutputType(format!("{:?}", other))),
+        };
+
+        match (ty, quantization) {
+            (TensorElementDataType::Float32, _) => Ok(OutputElement::Float32),
+            (TensorElementDataType::Float16, _) => Ok(OutputElement::Float16),
+            (TensorElementDataType::Int8, Some((scale, zero_point))) => Ok(OutputElement::Int8 { scale, zero_point }),
+            (TensorElementDataType::Uint8, Some((scale, zero_point))) => Ok(OutputElement::Uint8 { scale, zero_point }),
+            (ty, _) => Err(SemanticError::UnsupportedOutputType(format!("{:?}", ty))),
+        }
+    }
+
+    /// Extracts the output tensor and hands `f` an `f32` view of it. `f32` outputs are borrowed as they are,
+    /// the others are converted into a temporary array first.
+    pub fn with_f32<R>(&self, value: &ort::Value, f: impl FnOnce(ArrayViewD<f32>) -> R) -> Result<R, SemanticError> {
+        match *self {
+            OutputElement::Float32 => {
+                let tensor = value.extract_tensor::<f32>().map_err(SemanticError::TensorExtract)?;
+                let view = &*tensor.view();
+                Ok(f(view.view()))
+            }
+            OutputElement::Float16 => {
+                let tensor = value.extract_tensor::<half::f16>().map_err(SemanticError::TensorExtract)?;
+                let converted = tensor.view().mapv(f32::from);
+                Ok(f(converted.view()))
+            }
+            OutputElement::Int8 { scale, zero_point } => {
+                let tensor = value.extract_tensor::<i8>().map_err(SemanticError::TensorExtract)?;
+                let converted = tensor.view().mapv(|q| dequantize(q as i32, scale, zero_point));
+                Ok(f(converted.view()))
+            }
+            OutputElement::Uint8 { scale, zero_point } => {
+                let tensor = value.extract_tensor::<u8>().map_err(SemanticError::TensorExtract)?;
+                let converted = tensor.view().mapv(|q| dequantize(q as i32, scale, zero_point));
+                Ok(f(converted.view()))
+            }
+        }
+    }
+}
+
+/// The model output of a run, resolved from the rank of the tensor. Most models emit per-token embeddings to pool,
+/// pooler outputs are already one embedding per sequence.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum OutputEmbeddings<'a> {
+    /// `(batch, sequence, hidden)` token embeddings.
+    Tokens(ArrayView3<'a, f32>),
+    /// `(batch, hidden)` embeddings the model pooled itself.
+    Pooled(ArrayView2<'a, f32>),
+}
+
+impl<'a> OutputEmbeddings<'a> {
+    /// Resolves an output of a run over `(batch, sequence)` inputs. A 2D output is already pooled when it has one row
+    /// per sequence, and `(sequence, hidden)` token embeddings without a batch axis when a single sequence was run.
+    /// Pooled outputs with extra axes of length 1, like `(batch, 1, hidden)`, are squeezed to `(batch, hidden)`.
+    /// Views of any memory layout are accepted, nothing assumes the output is contiguous.
+    pub fn resolve(output: ArrayViewD<'a, f32>, (batch, sequence): (usize, usize)) -> Result<Self, SemanticError> {
+        match output.ndim() {
+            3 if output.shape()[1] == sequence => output.into_dimensionality::<Ix3>()
+                .map(OutputEmbeddings::Tokens)
+                .map_err(SemanticError::PoolingFailed),
+            rank if rank > 2 && output.shape()[1..rank - 1].iter().all(|length| *length == 1) => {
+                let mut output = output;
+                while output.ndim() > 2 {
+                    output = output.index_axis_move(Axis(1), 0);
+                }
+                Self::resolve(output, (batch, sequence))
+            }
+            3 => Err(incompatible_shape()),
+            2 => {
+                let output = output.into_dimensionality::<Ix2>().map_err(SemanticError::PoolingFailed)?;
+                match output.nrows() {
+                    rows if batch == 1 && rows == sequence => Ok(OutputEmbeddings::Tokens(output.insert_axis(Axis(0)))),
+                    rows if rows == batch => Ok(OutputEmbeddings::Pooled(output)),
+                    _ => Err(incompatible_shape()),
+                }
+            }
+            rank => Err(SemanticError::UnexpectedOutputRank(rank)),
+        }
+    }
+
+    /// Pools the token embeddings into `(batch, hidden)` embeddings, already pooled outputs are returned as they are.
+    pub fn pool(&self, pooling: PoolingStrategy, attention_mask: ArrayView2<i64>) -> Array2<f32> {
+        match self {
+            OutputEmbeddings::Tokens(token_embeddings) => pooling.pool(*token_embeddings, attention_mask),
+            OutputEmbeddings::Pooled(pooled) => pooled.to_owned(),
+        }
+    }
+
+    /// The token embeddings, which an already pooled output doesn't have anymore.
+    pub fn tokens(self) -> Result<ArrayView3<'a, f32>, SemanticError> {
+        match self {
+            OutputEmbeddings::Tokens(token_embeddings) => Ok(token_embeddings),
+            OutputEmbeddings::Pooled(_) => Err(SemanticError::UnexpectedOutputRank(2)),
+        }
+    }
+}
+
+fn incompatible_shape() -> SemanticError {
+    SemanticError::PoolingFailed(ndarray::ShapeError::from_kind(ndarray::ErrorKind::IncompatibleShape))
+}
+
+/// Reads the output's quantization parameters from the model metadata, if it has both of them.
+pub(crate) fn quantization_params(session: &ort::Session) -> Option<(f32, i32)> {
+    let metadata = session.metadata().ok()?;
+    let scale = metadata.custom(QUANTIZATION_SCALE_KEY).ok()??.parse().ok()?;
+    let zero_point = metadata.custom(QUANTIZATION_ZERO_POINT_KEY).ok()??.parse().ok()?;
+
+    Some((scale, zero_point))
+}
+
+fn dequantize(value: i32, scale: f32, zero_point: i32) -> f32 {
+    (value - zero_point) as f32 * scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(ty: TensorElementDataType) -> ort::Output {
+        ort::Output {
+            name: "last_hidden_state".to_string(),
+            output_type: ort::ValueType::Tensor { ty, dimensions: vec![-1, -1, 384] },
+        }
+    }
+
+    fn named_output(name: &str, dimensions: Vec<i64>) -> ort::Output {
+        ort::Output {
+            name: name.to_string(),
+            output_type: ort::ValueType::Tensor { ty: TensorElementDataType::Float32, dimensions },
+        }
+    }
+
+    #[test]
+    fn should_select_output_of_two_output_model() {
+        let declared = vec![named_output("pooler_output", vec![-1, 384]), named_output("last_hidden_state", vec![-1, -1, 384])];
+
+        assert_eq!(OutputSelector::Auto.select(&declared).unwrap().name, "last_hidden_state");
+        assert_eq!(OutputSelector::Name("pooler_output".to_string()).select(&declared).unwrap().name, "pooler_output");
+        assert_eq!(OutputSelector::Index(1).select(&declared).unwrap().name, "last_hidden_state");
+    }
+
+    #[test]
+    fn should_fall_back_to_first_output() {
+        let declared = vec![named_output("output_0", vec![-1, -1, 384]), named_output("output_1", vec![-1, 384])];
+
+        assert_eq!(OutputSelector::Auto.select(&declared).unwrap().name, "output_0");
+    }
+
+    #[test]
+    fn should_report_missing_output() {
+        let declared = vec![named_output("last_hidden_state", vec![-1, -1, 384])];
+
+        assert!(matches!(OutputSelector::Name("sentence_embedding".to_string()).select(&declared),
+            Err(SemanticError::MissingModelOutput(name)) if name == "sentence_embedding"));
+        assert!(matches!(OutputSelector::Index(2).select(&declared), Err(SemanticError::MissingModelOutput(_))));
+        assert!(matches!(OutputSelector::Auto.select(&[]), Err(SemanticError::MissingModelOutput(_))));
+    }
+
+    #[test]
+    fn should_read_declared_dim() {
+        assert_eq!(ModelOutput::resolve(&named_output("x", vec![-1, -1, 384]), None).unwrap().declared_dim, Some(384));
+        assert_eq!(ModelOutput::resolve(&named_output("x", vec![-1, -1, -1]), None).unwrap().declared_dim, None);
+    }
+
+    #[test]
+    fn should_detect_cross_encoder_from_scalar_output() {
+        let logits = ModelOutput::resolve(&named_output("logits", vec![-1, 1]), None).unwrap();
+        assert_eq!(logits.kind, ModelKind::CrossEncoder);
+        assert_eq!(logits.declared_dim, Some(1));
+        assert_eq!(ModelOutput::resolve(&named_output("scores", vec![-1]), None).unwrap().kind, ModelKind::CrossEncoder);
+
+        assert_eq!(ModelOutput::resolve(&named_output("last_hidden_state", vec![-1, -1, 384]), None).unwrap().kind, ModelKind::BiEncoder);
+        assert_eq!(ModelOutput::resolve(&named_output("pooler_output", vec![-1, 384]), None).unwrap().kind, ModelKind::BiEncoder);
+    }
+
+    #[test]
+    fn should_read_pair_score() {
+        assert_eq!(pair_score(ndarray::array![[2.5f32]].into_dyn().view()).unwrap(), 2.5);
+        assert_eq!(pair_score(ndarray::array![-1.0f32].into_dyn().view()).unwrap(), -1.0);
+
+        assert!(matches!(pair_score(ndarray::array![[0.5f32, 0.5]].into_dyn().view()), Err(SemanticError::PoolingFailed(_))));
+        assert!(matches!(pair_score(ndarray::Array3::<f32>::zeros((1, 1, 1)).into_dyn().view()), Err(SemanticError::UnexpectedOutputRank(3))));
+    }
+
+    #[test]
+    fn should_resolve_float_outputs() {
+        assert_eq!(OutputElement::resolve(&output(TensorElementDataType::Float32), None).unwrap(), OutputElement::Float32);
+        assert_eq!(OutputElement::resolve(&output(TensorElementDataType::Float16), None).unwrap(), OutputElement::Float16);
+    }
+
+    #[test]
+    fn should_require_quantization_params_for_int8() {
+        let int8 = output(TensorElementDataType::Int8);
+
+        assert!(matches!(OutputElement::resolve(&int8, None), Err(SemanticError::UnsupportedOutputType(_))));
+        assert_eq!(
+            OutputElement::resolve(&int8, Some((0.5, 3))).unwrap(),
+            OutputElement::Int8 { scale: 0.5, zero_point: 3 }
+        );
+    }
+
+    #[test]
+    fn should_reject_integer_outputs() {
+        let int64 = output(TensorElementDataType::Int64);
+
+        assert!(matches!(OutputElement::resolve(&int64, Some((1.0, 0))), Err(SemanticError::UnsupportedOutputType(_))));
+    }
+
+    #[test]
+    fn should_pool_token_embeddings_with_or_without_batch_axis() {
+        let batched = ndarray::array![[[1.0, 2.0], [3.0, 4.0], [9.0, 9.0]]];
+        let unbatched = ndarray::array![[1.0, 2.0], [3.0, 4.0], [9.0, 9.0]];
+        let mask = ndarray::array![[1, 1, 0]];
+
+        for output in [batched.into_dyn(), unbatched.into_dyn()] {
+            let resolved = OutputEmbeddings::resolve(output.view(), (1, 3)).unwrap();
+
+            assert!(matches!(resolved, OutputEmbeddings::Tokens(_)));
+            assert_eq!(resolved.pool(PoolingStrategy::Mean, mask.view()), ndarray::array![[2.0, 3.0]]);
+        }
+    }
+
+    #[test]
+    fn should_pass_through_pooled_output() {
+        let pooled = ndarray::array![[0.5, 0.25], [1.0, -1.0]].into_dyn();
+        let mask = ndarray::array![[1, 1, 1], [1, 1, 0]];
+
+        let resolved = OutputEmbeddings::resolve(pooled.view(), (2, 3)).unwrap();
+
+        assert_eq!(resolved.pool(PoolingStrategy::Cls, mask.view()), ndarray::array![[0.5, 0.25], [1.0, -1.0]]);
+        assert!(matches!(resolved.tokens(), Err(SemanticError::UnexpectedOutputRank(2))));
+    }
+
+    #[test]
+    fn should_squeeze_pooled_output_with_extra_axes() {
+        let mask = ndarray::array![[1, 1, 1], [1, 1, 0]];
+        // `(hidden, 1, batch)` in memory, so the `(batch, 1, hidden)` view isn't contiguous
+        let transposed = ndarray::array![[[0.5, 1.0]], [[0.25, -1.0]]];
+        let pooled = transposed.view().permuted_axes([2, 1, 0]).into_dyn();
+        assert!(pooled.as_slice().is_none());
+
+        let resolved = OutputEmbeddings::resolve(pooled, (2, 3)).unwrap();
+
+        assert!(matches!(resolved, OutputEmbeddings::Pooled(_)));
+        assert_eq!(resolved.pool(PoolingStrategy::Cls, mask.view()), ndarray::array![[0.5, 0.25], [1.0, -1.0]]);
+
+        let four_d = ndarray::Array4::<f32>::ones((2, 1, 1, 4)).into_dyn();
+        assert_eq!(OutputEmbeddings::resolve(four_d.view(), (2, 3)).unwrap().pool(PoolingStrategy::Cls, mask.view()).dim(), (2, 4));
+    }
+
+    #[test]
+    fn should_reject_unexpected_output_shapes() {
+        let flat = ndarray::Array1::<f32>::zeros(4).into_dyn();
+        let four_d = ndarray::Array4::<f32>::zeros((1, 3, 2, 2)).into_dyn();
+        let mismatched = ndarray::Array2::<f32>::zeros((5, 2)).into_dyn();
+        let wrong_sequence = ndarray::Array3::<f32>::zeros((1, 4, 2)).into_dyn();
+
+        assert!(matches!(OutputEmbeddings::resolve(flat.view(), (1, 3)), Err(SemanticError::UnexpectedOutputRank(1))));
+        assert!(matches!(OutputEmbeddings::resolve(four_d.view(), (1, 3)), Err(SemanticError::UnexpectedOutputRank(4))));
+        assert!(matches!(OutputEmbeddings::resolve(mismatched.view(), (2, 3)), Err(SemanticError::PoolingFailed(_))));
+        assert!(matches!(OutputEmbeddings::resolve(wrong_sequence.view(), (1, 3)), Err(SemanticError::PoolingFailed(_))));
+    }
+
+    #[test]
+    fn should_dequantize_affine() {
+        assert_eq!(dequantize(131, 0.5, 128), 1.5);
+        assert_eq!(dequantize(-4, 0.25, 0), -1.0);
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "stream")]
+use futures::stream::{BoxStream, Stream, StreamExt};
+
+use crate::embedding::Embedding;
+use crate::embedding::config::SemanticConfig;
+use crate::embedding::semantic::{Semantic, SemanticError};
+
+/// Several [`Semantic`]s built from the same model, handed out round-robin so worker threads run on separate
+/// ORT sessions instead of contending for one session's thread pool.
+///
+/// Every session is a full copy of the model, so memory grows linearly with the pool size. Cloning the pool is
+/// cheap, clones share the sessions and the round-robin position.
+///
+/// Example:
+/// ```rust
+/// use inference_core::SemanticBuilder;
+///
+/// let model = std::fs::read("../model/model.onnx").unwrap();
+/// let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+///
+/// let pool = SemanticBuilder::new(model, tokenizer_data)
+///     .pool_size(4)
+///     .build_pool()
+///     .unwrap();
+/// let embedding = pool.embed("Hello world!").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct SemanticPool {
+    semantics: Arc<Vec<Semantic>>,
+    next: Arc<AtomicUsize>,
+}
+
+impl SemanticPool {
+    pub(crate) fn new(model: Vec<u8>, tokenizer_data: Vec<u8>, config: SemanticConfig, size: usize) -> Result<SemanticPool, SemanticError> {
+        if size == 0 {
+            return Err(SemanticError::InvalidPoolSize(size));
+        }
+
+        let semantics = (0..size)
+            .map(|_| Semantic::init_semantic_with_config(model.clone(), tokenizer_data.clone(), config.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SemanticPool { semantics: Arc::new(semantics), next: Arc::default() })
+    }
+
+    /// The number of sessions in the pool.
+    pub fn size(&self) -> usize {
+        self.semantics.len()
+    }
+
+    /// See [`Semantic::embedding_dim`].
+    pub fn embedding_dim(&self) -> usize {
+        self.semantics[0].embedding_dim()
+    }
+
+    /// Embeds the sequence with the next session in the pool, see [`Semantic::embed`].
+    pub fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
+        self.pick().embed(sequence)
+    }
+
+    /// Embeds the batch with the next session in the pool, see [`Semantic::embed_batch`].
+    pub fn embed_batch(&self, sequences: &[&str]) -> Result<Vec<Embedding>, SemanticError> {
+        self.pick().embed_batch(sequences)
+    }
+
+    /// Embeds every text of the input stream, running up to `concurrency` inferences at once on Tokio's blocking
+    /// thread pool. With `ordered`, embeddings come out in the order of their inputs, otherwise as soon as they're
+    /// ready. Inputs are pulled lazily, so at most `concurrency` texts are held in memory. Needs the `stream`
+    /// feature and a Tokio runtime.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use futures::StreamExt;
+    /// use inference_core::SemanticBuilder;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let model = std::fs::read("../model/model.onnx").unwrap();
+    /// let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+    /// let pool = SemanticBuilder::new(model, tokenizer_data).pool_size(2).build_pool().unwrap();
+    ///
+    /// let inputs = futures::stream::iter(vec!["Hello".to_string(), "World".to_string()]);
+    /// let embeddings: Vec<_> = pool.embed_stream(inputs, 2, true).collect().await;
+    /// # });
+    /// ```
+    #[cfg(feature = "stream")]
+    pub fn embed_stream<S>(&self, inputs: S, concurrency: usize, ordered: bool) -> BoxStream<'static, Result<Embedding, SemanticError>>
+    where
+        S: Stream<Item = String> + Send + 'static,
+    {
+        let pool = self.clone();
+        let tasks = inputs.map(move |text| {
+            let pool = pool.clone();
+            async move {
+                tokio::task::spawn_blocking(move || pool.embed(&text))
+                    .await
+                    .map_err(|e| SemanticError::AsyncTaskFailed(e.to_string()))?
+            }
+        });
+
+        // a limit of zero would never poll any input
+        let concurrency = concurrency.max(1);
+        if ordered {
+            tasks.buffered(concurrency).boxed()
+        } else {
+            tasks.buffer_unordered(concurrency).boxed()
+        }
+    }
+
+    fn pick(&self) -> &Semantic {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.semantics.len();
+        &self.semantics[index]
+    }
+}
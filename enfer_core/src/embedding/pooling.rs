@@ -0,0 +1,208 @@
+use ndarray::{Array2, ArrayView2, ArrayView3, Axis};
+
+/// How the token embeddings produced by the model are reduced into a single sentence embedding.
+/// Pick the one the model was trained with, sentence-transformers models document it in `1_Pooling/config.json`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoolingStrategy {
+    /// Average of the token embeddings, ignoring padding.
+    #[default]
+    Mean,
+    /// The embedding of the first unmasked (`[CLS]`) token.
+    Cls,
+    /// Element-wise maximum over the token embeddings, ignoring padding.
+    Max,
+    /// Sum of the token embeddings divided by the square root of the sequence length.
+    MeanSqrtLen,
+}
+
+/// Which tokens of the sequence are pooled, on top of the attention mask.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoolingScope {
+    /// Every token, including special tokens such as `[CLS]` and `[SEP]`.
+    #[default]
+    All,
+    /// Only the tokens at positions `[start, end)` of the tokenized sequence, counting special tokens, e.g. the first
+    /// `K` tokens holding the query part of the input.
+    Range { start: usize, end: usize },
+    /// Only the tokens of the text, leaving out the special tokens the tokenizer added.
+    NonSpecial,
+}
+
+impl PoolingScope {
+    /// Narrows the attention mask of one unpadded sequence to the scope. A sequence with no token in scope
+    /// pools to zeros.
+    pub(crate) fn mask<'a>(&self, attention_mask: &'a [u32], special_tokens_mask: &'a [u32]) -> impl Iterator<Item = i64> + 'a {
+        let scope = *self;
+
+        attention_mask.iter()
+            .zip(special_tokens_mask)
+            .enumerate()
+            .map(move |(position, (attention, special))| {
+                let in_scope = match scope {
+                    PoolingScope::All => true,
+                    PoolingScope::Range { start, end } => (start..end).contains(&position),
+                    PoolingScope::NonSpecial => *special == 0,
+                };
+
+                if in_scope { *attention as i64 } else { 0 }
+            })
+    }
+}
+
+impl PoolingStrategy {
+    /// Pools `(batch, sequence, hidden)` token embeddings into `(batch, hidden)` sentence embeddings.
+    pub fn pool(&self, token_embeddings: ArrayView3<f32>, attention_mask: ArrayView2<i64>) -> Array2<f32> {
+        match self {
+            PoolingStrategy::Mean => mean_pooling(token_embeddings, attention_mask),
+            PoolingStrategy::Cls => cls_pooling(token_embeddings, attention_mask),
+            PoolingStrategy::Max => max_pooling(token_embeddings, attention_mask),
+            PoolingStrategy::MeanSqrtLen => mean_sqrt_len_pooling(token_embeddings, attention_mask),
+        }
+    }
+}
+
+/// Averages the token embeddings over the sequence axis, weighting every position by its attention mask value.
+/// Padded positions (mask `0`) contribute nothing, so a padded sequence pools to the same vector as the unpadded one.
+///
+/// `token_embeddings` is `(batch, sequence, hidden)` and `attention_mask` is `(batch, sequence)`,
+/// the result is `(batch, hidden)`.
+pub fn mean_pooling(token_embeddings: ArrayView3<f32>, attention_mask: ArrayView2<i64>) -> Array2<f32> {
+    let mask = attention_mask.mapv(|item| item as f32).insert_axis(Axis(2));
+
+    let summed = (&token_embeddings * &mask).sum_axis(Axis(1));
+    let counts = mask.sum_axis(Axis(1)).mapv(|count| count.max(1e-9));
+
+    summed / counts
+}
+
+/// Takes the embedding of the first unmasked token of every sequence, which is `[CLS]` for BERT-like tokenizers,
+/// also when the sequence is left-padded. A sequence without any unmasked token pools to zeros.
+pub fn cls_pooling(token_embeddings: ArrayView3<f32>, attention_mask: ArrayView2<i64>) -> Array2<f32> {
+    let (batch_size, _, hidden_size) = token_embeddings.dim();
+    let mut pooled = Array2::zeros((batch_size, hidden_size));
+
+    for ((tokens, mask), mut row) in token_embeddings.outer_iter()
+        .zip(attention_mask.outer_iter())
+        .zip(pooled.outer_iter_mut()) {
+        if let Some(first) = mask.iter().position(|mask| *mask != 0) {
+            row.assign(&tokens.row(first));
+        }
+    }
+
+    pooled
+}
+
+/// Takes the element-wise maximum over the unmasked token embeddings. A sequence without any unmasked token pools to zeros.
+pub fn max_pooling(token_embeddings: ArrayView3<f32>, attention_mask: ArrayView2<i64>) -> Array2<f32> {
+    let (batch_size, _, hidden_size) = token_embeddings.dim();
+    let mut pooled = Array2::zeros((batch_size, hidden_size));
+
+    for ((tokens, mask), mut row) in token_embeddings.outer_iter()
+        .zip(attention_mask.outer_iter())
+        .zip(pooled.outer_iter_mut()) {
+        let mut unmasked = tokens.outer_iter()
+            .zip(mask.iter())
+            .filter(|(_, mask)| **mask != 0)
+            .map(|(token, _)| token);
+
+        if let Some(first) = unmasked.next() {
+            row.assign(&first);
+            for token in unmasked {
+                row.zip_mut_with(&token, |max, value| *max = max.max(*value));
+            }
+        }
+    }
+
+    pooled
+}
+
+/// Sums the unmasked token embeddings and divides by the square root of the number of unmasked tokens.
+pub fn mean_sqrt_len_pooling(token_embeddings: ArrayView3<f32>, attention_mask: ArrayView2<i64>) -> Array2<f32> {
+    let mask = attention_mask.mapv(|item| item as f32).insert_axis(Axis(2));
+
+    let summed = (&token_embeddings * &mask).sum_axis(Axis(1));
+    let lengths = mask.sum_axis(Axis(1)).mapv(|count| count.max(1e-9).sqrt());
+
+    summed / lengths
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array3};
+
+    use super::*;
+
+    #[test]
+    fn should_ignore_padded_positions() {
+        // two real tokens padded to a length of four
+        let token_embeddings: Array3<f32> = array![[[1.0, 2.0], [3.0, 4.0], [100.0, 100.0], [-100.0, 50.0]]];
+        let attention_mask = array![[1, 1, 0, 0]];
+
+        let pooled = mean_pooling(token_embeddings.view(), attention_mask.view());
+
+        assert_eq!(pooled, array![[2.0, 3.0]]);
+    }
+
+    #[test]
+    fn should_match_plain_mean_without_padding() {
+        let token_embeddings: Array3<f32> = array![[[1.0, 2.0], [3.0, 4.0], [5.0, 9.0]]];
+        let attention_mask = array![[1, 1, 1]];
+
+        let pooled = mean_pooling(token_embeddings.view(), attention_mask.view());
+
+        assert_eq!(pooled, token_embeddings.mean_axis(Axis(1)).unwrap());
+    }
+
+    #[test]
+    fn should_pool_with_each_strategy() {
+        let token_embeddings: Array3<f32> = array![[[1.0, 8.0], [3.0, 4.0], [100.0, 100.0]]];
+        let attention_mask = array![[1, 1, 0]];
+
+        let cls = PoolingStrategy::Cls.pool(token_embeddings.view(), attention_mask.view());
+        assert_eq!(cls, array![[1.0, 8.0]]);
+
+        let max = PoolingStrategy::Max.pool(token_embeddings.view(), attention_mask.view());
+        assert_eq!(max, array![[3.0, 8.0]]);
+
+        let sqrt_len = PoolingStrategy::MeanSqrtLen.pool(token_embeddings.view(), attention_mask.view());
+        assert_eq!(sqrt_len, array![[4.0 / 2f32.sqrt(), 12.0 / 2f32.sqrt()]]);
+    }
+
+    #[test]
+    fn should_narrow_mask_to_scope() {
+        let attention_mask = [1, 1, 1, 1, 0];
+        let special_tokens_mask = [1, 0, 0, 1, 0];
+
+        let mask = |scope: PoolingScope| scope.mask(&attention_mask, &special_tokens_mask).collect::<Vec<_>>();
+
+        assert_eq!(mask(PoolingScope::All), vec![1, 1, 1, 1, 0]);
+        assert_eq!(mask(PoolingScope::Range { start: 1, end: 3 }), vec![0, 1, 1, 0, 0]);
+        assert_eq!(mask(PoolingScope::Range { start: 3, end: 9 }), vec![0, 0, 0, 1, 0]);
+        assert_eq!(mask(PoolingScope::NonSpecial), vec![0, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn should_pool_only_tokens_in_scope() {
+        let token_embeddings: Array3<f32> = array![[[1.0, 8.0], [3.0, 4.0], [5.0, 0.0]]];
+        let scoped = Array2::from_shape_vec((1, 3), PoolingScope::Range { start: 0, end: 2 }.mask(&[1, 1, 1], &[0, 0, 0]).collect()).unwrap();
+
+        let full = mean_pooling(token_embeddings.view(), array![[1, 1, 1]].view());
+        let prefix = mean_pooling(token_embeddings.view(), scoped.view());
+
+        assert_eq!(prefix, array![[2.0, 6.0]]);
+        assert_ne!(prefix, full);
+    }
+
+    #[test]
+    fn should_pool_left_padded_sequences_like_right_padded_ones() {
+        let right: Array3<f32> = array![[[1.0, 8.0], [3.0, 4.0], [100.0, 100.0]]];
+        let left: Array3<f32> = array![[[100.0, 100.0], [1.0, 8.0], [3.0, 4.0]]];
+
+        for strategy in [PoolingStrategy::Mean, PoolingStrategy::Cls, PoolingStrategy::Max, PoolingStrategy::MeanSqrtLen] {
+            assert_eq!(
+                strategy.pool(left.view(), array![[0, 1, 1]].view()),
+                strategy.pool(right.view(), array![[1, 1, 0]].view())
+            );
+        }
+    }
+}
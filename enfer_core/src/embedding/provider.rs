@@ -0,0 +1,211 @@
+#[cfg(feature = "tensorrt")]
+use std::path::PathBuf;
+
+use ort::SessionBuilder;
+
+use crate::embedding::config::Precision;
+
+/// The hardware backend ORT runs the model on. Accelerated providers are behind cargo features of the same name,
+/// and fall back to the CPU with a logged warning when they can't be registered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ExecutionProvider {
+    #[default]
+    Cpu,
+    /// NVIDIA GPUs through CUDA. `device_id` is the CUDA device index, `0` being the first GPU.
+    #[cfg(feature = "cuda")]
+    Cuda { device_id: i32 },
+    /// Apple's CoreML on macOS/iOS, which can dispatch to the Neural Engine.
+    #[cfg(feature = "coreml")]
+    CoreML,
+    /// Any DirectX 12 GPU on Windows. `device_id` is the DXGI adapter index, `0` being the default adapter.
+    #[cfg(feature = "directml")]
+    DirectML { device_id: i32 },
+    /// Intel CPUs and integrated GPUs through OpenVINO. `device_type` is the OpenVINO device, such as `CPU`, `GPU`
+    /// or `AUTO` to let OpenVINO pick one.
+    #[cfg(feature = "openvino")]
+    OpenVINO { device_type: String },
+    /// NVIDIA GPUs through TensorRT, falling back to CUDA on the same device and then to the CPU. Building the
+    /// TensorRT engine can take minutes, so with an `engine_cache_dir` the engine is written there and reused by
+    /// later sessions of the same model on the same GPU.
+    #[cfg(feature = "tensorrt")]
+    TensorRT { device_id: i32, engine_cache_dir: Option<PathBuf> },
+}
+
+/// Which kind of backend an [`ExecutionProvider`] is, without its options. Every kind exists regardless of the
+/// enabled cargo features, so it can be matched on and handed across an FFI boundary the same way everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutionProviderKind {
+    Cpu,
+    Cuda,
+    CoreML,
+    DirectML,
+    OpenVINO,
+    TensorRT,
+}
+
+impl ExecutionProvider {
+    pub fn kind(&self) -> ExecutionProviderKind {
+        match self {
+            ExecutionProvider::Cpu => ExecutionProviderKind::Cpu,
+            #[cfg(feature = "cuda")]
+            ExecutionProvider::Cuda { .. } => ExecutionProviderKind::Cuda,
+            #[cfg(feature = "coreml")]
+            ExecutionProvider::CoreML => ExecutionProviderKind::CoreML,
+            #[cfg(feature = "directml")]
+            ExecutionProvider::DirectML { .. } => ExecutionProviderKind::DirectML,
+            #[cfg(feature = "openvino")]
+            ExecutionProvider::OpenVINO { .. } => ExecutionProviderKind::OpenVINO,
+            #[cfg(feature = "tensorrt")]
+            ExecutionProvider::TensorRT { .. } => ExecutionProviderKind::TensorRT,
+        }
+    }
+
+    /// Registers the provider on the session builder, going down its fallback chain until one registers, and returns
+    /// the provider that is actually used. Providers that compute in fp16 are asked to when `precision` is `Fp16`.
+    pub(crate) fn register(&self, builder: &SessionBuilder, precision: Precision) -> ExecutionProvider {
+        let mut provider = self.clone();
+        loop {
+            match provider.try_register(builder, precision) {
+                Ok(()) => {
+                    log::info!("using the {:?} execution provider", provider);
+                    return provider;
+                }
+                Err(reason) => match provider.fallback() {
+                    Some(fallback) => {
+                        log::warn!("failed to register the {:?} execution provider, falling back to {:?}: {}", provider, fallback, reason);
+                        provider = fallback;
+                    }
+                    None => {
+                        log::warn!("failed to register the {:?} execution provider: {}", provider, reason);
+                        return provider;
+                    }
+                },
+            }
+        }
+    }
+
+    /// The provider tried next when this one can't be registered. ORT always runs on the CPU, so the chain ends there.
+    fn fallback(&self) -> Option<ExecutionProvider> {
+        match self {
+            ExecutionProvider::Cpu => None,
+            #[cfg(feature = "tensorrt")]
+            ExecutionProvider::TensorRT { device_id, .. } => Some(ExecutionProvider::Cuda { device_id: *device_id }),
+            #[allow(unreachable_patterns)]
+            _ => Some(ExecutionProvider::Cpu),
+        }
+    }
+
+    #[cfg_attr(not(feature = "tensorrt"), allow(unused_variables))]
+    fn try_register(&self, builder: &SessionBuilder, precision: Precision) -> Result<(), String> {
+        match self {
+            ExecutionProvider::Cpu => try_register(ort::CPUExecutionProvider::default(), builder),
+            #[cfg(feature = "cuda")]
+            ExecutionProvider::Cuda { device_id } => {
+                try_register(ort::CUDAExecutionProvider::default().with_device_id(*device_id), builder)
+            }
+            #[cfg(feature = "coreml")]
+            ExecutionProvider::CoreML => try_register(ort::CoreMLExecutionProvider::default(), builder),
+            #[cfg(feature = "directml")]
+            ExecutionProvider::DirectML { device_id } => {
+                try_register(ort::DirectMLExecutionProvider::default().with_device_id(*device_id), builder)
+            }
+            #[cfg(feature = "openvino")]
+            ExecutionProvider::OpenVINO { device_type } => {
+                try_register(ort::OpenVINOExecutionProvider::default().with_device_type(device_type.as_str()), builder)
+            }
+            #[cfg(feature = "tensorrt")]
+            ExecutionProvider::TensorRT { device_id, engine_cache_dir } => {
+                let mut provider = ort::TensorRTExecutionProvider::default()
+                    .with_device_id(*device_id)
+                    .with_fp16(precision == Precision::Fp16);
+                if let Some(dir) = engine_cache_dir {
+                    std::fs::create_dir_all(dir).map_err(|e| format!("can't create the engine cache directory {}: {}", dir.display(), e))?;
+                    provider = provider.with_engine_cache(true).with_engine_cache_path(dir.to_string_lossy());
+                }
+                try_register(provider, builder)
+            }
+        }
+    }
+}
+
+impl ExecutionProvider {
+    /// Returns the precision the provider will actually compute in, falling back to fp32 with a logged warning
+    /// when fp16 is requested from a provider that can't compute in it.
+    pub(crate) fn resolve_precision(&self, requested: Precision) -> Precision {
+        if requested == Precision::Fp16 && !self.supports_fp16() {
+            log::warn!("the {:?} execution provider doesn't support fp16 compute, falling back to fp32", self);
+            return Precision::Fp32;
+        }
+
+        requested
+    }
+
+    fn supports_fp16(&self) -> bool {
+        match self {
+            ExecutionProvider::Cpu => false,
+            #[cfg(feature = "cuda")]
+            ExecutionProvider::Cuda { .. } => false,
+            #[cfg(feature = "coreml")]
+            ExecutionProvider::CoreML => false,
+            #[cfg(feature = "directml")]
+            ExecutionProvider::DirectML { .. } => false,
+            #[cfg(feature = "openvino")]
+            ExecutionProvider::OpenVINO { .. } => false,
+            // TensorRT builds its engine in fp16 when asked to, the other providers run an fp32 model as it is
+            #[cfg(feature = "tensorrt")]
+            ExecutionProvider::TensorRT { .. } => true,
+        }
+    }
+}
+
+fn try_register(provider: impl ort::ExecutionProvider, builder: &SessionBuilder) -> Result<(), String> {
+    if !provider.is_available().unwrap_or(false) {
+        return Err(format!("{} is not available", provider.as_str()));
+    }
+
+    provider.register(builder).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_fall_back_to_fp32_without_fp16_support() {
+        assert_eq!(ExecutionProvider::Cpu.resolve_precision(Precision::Fp16), Precision::Fp32);
+        assert_eq!(ExecutionProvider::Cpu.resolve_precision(Precision::Fp32), Precision::Fp32);
+    }
+
+    #[test]
+    #[cfg(feature = "tensorrt")]
+    fn should_keep_fp16_for_tensorrt() {
+        let tensorrt = ExecutionProvider::TensorRT { device_id: 0, engine_cache_dir: None };
+
+        assert_eq!(tensorrt.resolve_precision(Precision::Fp16), Precision::Fp16);
+        assert_eq!(ExecutionProvider::Cuda { device_id: 0 }.resolve_precision(Precision::Fp16), Precision::Fp32);
+        assert_eq!(ExecutionProvider::Cpu.resolve_precision(Precision::Fp16), Precision::Fp32);
+    }
+
+    #[test]
+    #[cfg(feature = "tensorrt")]
+    fn should_report_kind_without_options() {
+        assert_eq!(ExecutionProvider::Cpu.kind(), ExecutionProviderKind::Cpu);
+        assert_eq!(ExecutionProvider::TensorRT { device_id: 0, engine_cache_dir: None }.kind(), ExecutionProviderKind::TensorRT);
+        assert_eq!(ExecutionProvider::Cuda { device_id: 1 }.kind(), ExecutionProviderKind::Cuda);
+    }
+
+    #[test]
+    fn should_end_fallback_chain_on_cpu() {
+        assert_eq!(ExecutionProvider::Cpu.fallback(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "tensorrt")]
+    fn should_fall_back_from_tensorrt_to_cuda_then_cpu() {
+        let tensorrt = ExecutionProvider::TensorRT { device_id: 1, engine_cache_dir: None };
+
+        let cuda = tensorrt.fallback().unwrap();
+        assert_eq!(cuda, ExecutionProvider::Cuda { device_id: 1 });
+        assert_eq!(cuda.fallback(), Some(ExecutionProvider::Cpu));
+    }
+}
@@ -1,69 +1,347 @@
-use std::fmt::{Display, Formatter};
-use std::mem::ManuallyDrop;
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
+#[cfg(not(feature = "wasm"))]
+use std::sync::Mutex;
 
-use ndarray::Axis;
-use ort::{ExecutionProviderDispatch, GraphOptimizationLevel, LoggingLevel, SessionBuilder};
+use ndarray::ArrayView2;
+use ort::SessionBuilder;
+#[cfg(not(feature = "wasm"))]
+use ort::ExecutionProviderDispatch;
+use tokenizers::{Encoding, PostProcessor, TruncationDirection, TruncationParams};
 
 use crate::embedding::Embedding;
+use crate::embedding::batching::plan_sub_batches;
+use crate::embedding::buffers::{BufferPool, InputBuffers};
+use crate::embedding::builder::SemanticBuilder;
+use crate::embedding::config::{EmbedOptions, LogLevel, Precision, SemanticConfig};
+use crate::embedding::info::ModelInfo;
+use crate::embedding::inputs::ModelInputs;
+use crate::embedding::math::normalize_in_place;
+use crate::embedding::outputs::{pair_score, quantization_params, ModelKind, ModelOutput, OutputEmbeddings};
+use crate::embedding::pooling::{PoolingScope, PoolingStrategy};
+use crate::embedding::provider::{ExecutionProvider, ExecutionProviderKind};
+use crate::embedding::sentences::split_sentences;
+use crate::embedding::stats::{LengthHistogram, LengthStats, ProcessMemory};
+use crate::embedding::tokenizer::{load_tokenizer, normalize_text};
+use crate::embedding::validation::validate_vocab;
 
+/// A tokenizer and an ORT session that turn text into [`Embedding`]s.
+///
+/// `Semantic` is `Send + Sync`, so one instance can be shared across threads (e.g. in an `Arc`) and `embed`
+/// called concurrently. ONNX Runtime allows concurrent `Run` calls on the same session, and the tokenizer and
+/// config are only read, so no lock is taken around inference. Concurrent calls share the session's intra-op
+/// thread pool, so they run in parallel but don't get more total CPU than a single session would use.
+///
+/// Cloning is cheap, clones share the same session and tokenizer.
+#[derive(Clone)]
 pub struct Semantic {
-    model_ref: &'static [u8],
     tokenizer: Arc<tokenizers::Tokenizer>,
     session: Arc<ort::Session>,
+    config: SemanticConfig,
+    inputs: ModelInputs,
+    output: ModelOutput,
+    embedding_dim: usize,
+    compute_precision: Precision,
+    execution_provider: ExecutionProvider,
+    buffers: Arc<BufferPool>,
+    lengths: Arc<LengthHistogram>,
 }
 
-impl Drop for Semantic {
-    fn drop(&mut self) {
-        unsafe {
-            ManuallyDrop::drop(&mut ManuallyDrop::new(self.model_ref));
-        }
-    }
+/// An embedding together with details about how its input was tokenized.
+#[derive(Debug, Clone)]
+pub struct EmbedOutput {
+    pub embedding: Embedding,
+    /// The number of tokens fed to the model, including special tokens.
+    pub token_count: usize,
+    /// Whether the input was cut at the max length, in which case its tail didn't contribute to the embedding.
+    pub truncated: bool,
 }
 
 impl Semantic {
+    /// Starts a [`SemanticBuilder`], the most flexible way to create a [`Semantic`].
+    pub fn builder(model: Vec<u8>, tokenizer_data: Vec<u8>) -> SemanticBuilder {
+        SemanticBuilder::new(model, tokenizer_data)
+    }
+
     pub async fn initialize(model: Vec<u8>, tokenizer_data: Vec<u8>) -> Result<Pin<Box<Semantic>>, SemanticError> {
-        let semantic = Self::init_semantic(model, tokenizer_data)?;
+        let semantic = Self::builder(model, tokenizer_data).build_async().await?;
+
+        Ok(Box::pin(semantic))
+    }
+
+    /// Like [`Semantic::initialize`], but with explicit options instead of the defaults.
+    pub async fn initialize_with(model: Vec<u8>, tokenizer_data: Vec<u8>, config: SemanticConfig) -> Result<Pin<Box<Semantic>>, SemanticError> {
+        let semantic = Self::builder(model, tokenizer_data).config(config).build_async().await?;
 
         Ok(Box::pin(semantic))
     }
 
     pub fn init_semantic(model: Vec<u8>, tokenizer_data: Vec<u8>) -> Result<Semantic, SemanticError> {
-        ort::init()
-            .with_name("Encode")
-            .with_log_level(LoggingLevel::Warning)
-            .with_execution_providers([ExecutionProviderDispatch::CPU(Default::default())])
-            .commit()
-            .map_err(|e| SemanticError::InitBuildOrtEnv)?;
-
-
-        let threads = if let Ok(v) = std::env::var("NUM_OMP_THREADS") {
-            str::parse(&v).unwrap_or(1)
-        } else {
-            1
-        };
+        Self::builder(model, tokenizer_data).build()
+    }
+
+    /// Reads the model and the `tokenizer.json` from disk and initializes a [`Semantic`] with the default options.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::Semantic;
+    ///
+    /// let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embedding = semantic.embed("Hello world!").unwrap();
+    /// ```
+    pub fn from_files(model_path: impl AsRef<Path>, tokenizer_path: impl AsRef<Path>) -> Result<Semantic, SemanticError> {
+        Self::init_semantic_with_paths(model_path, tokenizer_path)
+    }
+
+    /// The path counterpart of [`Semantic::init_semantic`], a failed read keeps the [`std::io::Error`] as its source.
+    pub fn init_semantic_with_paths(model_path: impl AsRef<Path>, tokenizer_path: impl AsRef<Path>) -> Result<Semantic, SemanticError> {
+        let model = std::fs::read(model_path).map_err(SemanticError::InitModelReadError)?;
+        let tokenizer_data = std::fs::read(tokenizer_path).map_err(SemanticError::InitTokenizerReadError)?;
+
+        Self::init_semantic(model, tokenizer_data)
+    }
+
+    pub fn init_semantic_with_config(model: Vec<u8>, tokenizer_data: Vec<u8>, config: SemanticConfig) -> Result<Semantic, SemanticError> {
+        init_environment(config.log_level)?;
+
+        let mut tokenizer = load_tokenizer(tokenizer_data, config.tokenizer_format)?;
+
+        // without truncation the length is checked after encoding, so overly long inputs can be reported
+        let truncation = config.truncate.then(|| TruncationParams { max_length: config.max_length, ..Default::default() });
+        tokenizer.with_truncation(truncation)
+            .map_err(SemanticError::InitTokenizerTruncation)?;
+        let tokenizer: Arc<tokenizers::Tokenizer> = tokenizer.into();
+
+        Self::with_session(tokenizer, &model, config)
+    }
+
+    /// Swaps in a new model, keeping the tokenizer and options. The new session is fully built, and the embedding
+    /// dimension re-read, before anything is replaced, so on error this [`Semantic`] keeps using the old model.
+    ///
+    /// Clones made earlier keep the old session alive until they are dropped, so calls already running on them
+    /// finish with the old model.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::Semantic;
+    ///
+    /// let mut semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// semantic.replace_model(std::fs::read("../model/model.onnx").unwrap()).unwrap();
+    /// ```
+    pub fn replace_model(&mut self, model: Vec<u8>) -> Result<(), SemanticError> {
+        *self = Self::with_session(self.tokenizer.clone(), &model, self.config.clone())?;
 
-        let tokenizer: Arc<tokenizers::Tokenizer> = tokenizers::Tokenizer::from_bytes(tokenizer_data)
-            .map_err(|e| SemanticError::TokenizeEncodeByteError)?.into();
+        Ok(())
+    }
+
+    fn with_session(tokenizer: Arc<tokenizers::Tokenizer>, model: &[u8], config: SemanticConfig) -> Result<Semantic, SemanticError> {
+        let threads = config.resolve_intra_threads()?;
+        let inter_threads = config.resolve_inter_threads()?;
+        if config.validate {
+            validate_vocab(model, tokenizer.get_vocab_size(true))?;
+        }
 
-        let model_ref = model.leak();
+        let mut session_builder = SessionBuilder::new()
+            .map_err(SemanticError::InitSessionBuilder)?
+            .with_optimization_level(config.resolve_optimization_level().into()).map_err(SemanticError::InitSessionOptimization)?
+            .with_intra_threads(threads).map_err(SemanticError::InitSessionThreads)?
+            .with_memory_pattern(config.enable_memory_pattern).map_err(SemanticError::InitSessionBuilder)?;
+        if config.deterministic {
+            session_builder = session_builder
+                .with_parallel_execution(false).map_err(SemanticError::InitSessionThreads)?
+                .with_inter_threads(1).map_err(SemanticError::InitSessionThreads)?;
+        } else if let Some(inter_threads) = inter_threads {
+            session_builder = session_builder
+                .with_parallel_execution(true).map_err(SemanticError::InitSessionThreads)?
+                .with_inter_threads(inter_threads).map_err(SemanticError::InitSessionThreads)?;
+        }
+        let provider = config.resolve_execution_provider().register(&session_builder, config.compute_precision);
+        let compute_precision = provider.resolve_precision(config.compute_precision);
+
+        // ORT copies the model into the session, so the bytes are freed once the session is built
+        let session = session_builder
+            .with_model_from_memory(model)
+            .map_err(SemanticError::InitSessionModel)?;
 
         let semantic = Self {
-            model_ref,
             tokenizer,
-            session: SessionBuilder::new()
-                .map_err(|e| SemanticError::InitSessionBuilder)?
-                .with_optimization_level(GraphOptimizationLevel::Level3).map_err(|e| SemanticError::InitSessionOptimization)?
-                .with_intra_threads(threads).map_err(|e| SemanticError::InitSessionThreads)?
-                .with_model_from_memory(model_ref)
-                .unwrap()
-                .into(),
+            inputs: ModelInputs::resolve(&session.inputs, &config.input_names)?,
+            output: ModelOutput::resolve(config.output.select(&session.outputs)?, quantization_params(&session))?,
+            session: session.into(),
+            config,
+            embedding_dim: 0,
+            compute_precision,
+            execution_provider: provider,
+            buffers: Arc::default(),
+            lengths: Arc::default(),
         };
+
+        let semantic = semantic.with_resolved_embedding_dim()?;
+        if semantic.config.warmup {
+            semantic.warmup()?;
+        }
+        semantic.lengths.reset();
+
         Ok(semantic)
     }
 
+    /// Reads the hidden size from the declared output shape, or embeds a dummy token when the model uses a dynamic axis.
+    /// The embeddings are that long unless [`SemanticConfig::output_dim`] truncates them.
+    fn with_resolved_embedding_dim(mut self) -> Result<Semantic, SemanticError> {
+        let output_dim = self.config.output_dim.take();
+        let hidden_size = match self.output.declared_dim {
+            Some(dim) => dim,
+            None => self.embed(DUMMY_INPUT)?.len(),
+        };
+        self.config.output_dim = output_dim;
+
+        self.embedding_dim = match output_dim {
+            Some(dim) if dim == 0 || dim > hidden_size => {
+                return Err(SemanticError::InvalidDimension { dim, available: hidden_size });
+            }
+            Some(dim) => dim,
+            None => hidden_size,
+        };
+
+        Ok(self)
+    }
+
+    /// The number of dimensions of the embeddings this model produces, known as soon as the [`Semantic`] is built.
+    pub fn embedding_dim(&self) -> usize {
+        self.embedding_dim
+    }
+
+    /// The number of tokens in the tokenizer's vocabulary, including added tokens such as `[CLS]`. It should match
+    /// the size of the model's token embedding table, a larger vocabulary usually means the tokenizer belongs to
+    /// another model. The tokenizer is fully parsed when the [`Semantic`] is built, so this never loads anything.
+    pub fn vocab_size(&self) -> usize {
+        self.tokenizer.get_vocab_size(true)
+    }
+
+    /// The longest sequence the model accepts, when its `input_ids` input declares a fixed sequence length.
+    /// Models exported with a dynamic sequence axis return `None`, even though they are usually limited by their
+    /// position embeddings. Inputs are truncated to [`SemanticConfig::max_length`] either way.
+    pub fn max_sequence_length(&self) -> Option<usize> {
+        self.inputs.declared_length
+    }
+
+    /// Whether the model embeds sequences or scores pairs of them, told apart by its selected output: a single scalar
+    /// per sequence, such as `(batch, 1)` logits, makes a cross-encoder. Cross-encoders are only run through
+    /// [`Semantic::score_pair`], while bi-encoders are run through every other method, and either fails with
+    /// [`SemanticError::WrongModelKind`] when used the other way around. A cross-encoder's embedding dimension is `1`.
+    pub fn model_kind(&self) -> ModelKind {
+        self.output.kind
+    }
+
+    /// Runs a single throwaway inference, so the latency of ORT's first run is paid now rather than on the first real request.
+    pub fn warmup(&self) -> Result<(), SemanticError> {
+        match self.output.kind {
+            ModelKind::BiEncoder => self.embed(DUMMY_INPUT).map(drop),
+            ModelKind::CrossEncoder => self.score_pair(DUMMY_INPUT, DUMMY_INPUT).map(drop),
+        }
+    }
+
+    /// Describes the loaded model: its metadata and the name, element type and shape of every input and output.
+    /// Handy to find out why a model's inputs or output aren't picked up.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::Semantic;
+    ///
+    /// let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// println!("{:#?}", semantic.model_info());
+    /// ```
+    pub fn model_info(&self) -> ModelInfo {
+        ModelInfo::read(&self.session)
+    }
+
+    /// The precision the model is computed in, which is `Fp32` when fp16 was requested but isn't supported by the
+    /// execution provider in use. fp16 outputs are converted, so embeddings are always `f32`.
+    pub fn compute_precision(&self) -> Precision {
+        self.compute_precision
+    }
+
+    /// The execution provider the session runs on, which is where the fallback chain of the requested
+    /// [`SemanticConfig::execution_provider`] ended, e.g. CUDA when TensorRT couldn't be registered.
+    pub fn execution_provider(&self) -> &ExecutionProvider {
+        &self.execution_provider
+    }
+
+    /// The kind of [`Semantic::execution_provider`], e.g. to check that an accelerated provider wasn't replaced by
+    /// the CPU because it failed to register.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, ExecutionProviderKind, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// assert_eq!(semantic.active_provider(), ExecutionProviderKind::Cpu);
+    /// ```
+    pub fn active_provider(&self) -> ExecutionProviderKind {
+        self.execution_provider.kind()
+    }
+
+    /// The token counts of every sequence tokenized since the [`Semantic`] was built or [`Semantic::reset_stats`] was
+    /// last called, shared by its clones. Empty unless [`SemanticConfig::collect_stats`] is enabled.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::Semantic;
+    ///
+    /// let model = std::fs::read("../model/model.onnx").unwrap();
+    /// let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+    /// let semantic = Semantic::builder(model, tokenizer_data).collect_stats(true).build().unwrap();
+    ///
+    /// semantic.embed_batch(&["Hello world!", "How are you?"]).unwrap();
+    /// assert_eq!(semantic.stats().count, 2);
+    /// ```
+    pub fn stats(&self) -> LengthStats {
+        self.lengths.snapshot()
+    }
+
+    /// Forgets the token counts recorded so far.
+    pub fn reset_stats(&self) {
+        self.lengths.reset();
+    }
+
+    /// The current and peak resident memory of the process, to watch how much it grows in a long-running service.
+    /// `ort` 2.0.0-alpha.1 keeps a session's allocator private and has no binding for ORT's allocator statistics, so
+    /// these are process-wide figures read from `/proc/self/status`: ORT's arenas, all sessions and everything else the
+    /// process allocated. Only Linux reports them, other platforms (macOS included) return `None`. GPU memory isn't
+    /// included.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// semantic.embed("Hello world!").unwrap();
+    /// if let Some(memory) = semantic.process_memory() {
+    ///     println!("{} MiB now, {} MiB at peak", memory.resident_bytes >> 20, memory.peak_resident_bytes >> 20);
+    /// }
+    /// ```
+    pub fn process_memory(&self) -> Option<ProcessMemory> {
+        ProcessMemory::current()
+    }
+
+    /// Sets the pooling strategy used by [`Semantic::embed`] and [`Semantic::embed_batch`], defaults to [`PoolingStrategy::Mean`].
+    pub fn with_pooling(mut self, pooling: PoolingStrategy) -> Self {
+        self.config.pooling = pooling;
+        self
+    }
+
+    /// When enabled, every embedding is L2-normalized before it is returned, disabled by default.
+    pub fn with_normalization(mut self, normalize: bool) -> Self {
+        self.config.normalize = normalize;
+        self
+    }
+
     /// Embeds a sequence of text into a vector of xxx floats. The xxx floats are the embedding of the sequence.
+    /// Empty or whitespace-only text fails with [`SemanticError::EmptyInput`], and text the tokenizer turns into no
+    /// tokens at all, which can happen without special tokens, with [`SemanticError::EmptyTokenization`].
+    ///
+    /// With the `tracing` feature, every call is recorded in an `embed` span with its token count.
     ///
     /// Example:
     /// ```rust
@@ -73,79 +351,1221 @@ impl Semantic {
     /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
     /// let embedding = semantic.embed("Hello world!").unwrap();
     /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(tokens)))]
     pub fn embed(&self, sequence: &str) -> Result<Embedding, SemanticError> {
-        let encoding = self.tokenizer.encode(sequence, true)
-            .map_err(|_| SemanticError::TokenizeEncodeError)?;
-
-        let input_ids = encoding.get_ids().iter().map(|item| *item as i64).collect::<Vec<_>>();
-        let attention_mask = encoding.get_attention_mask().iter().map(|item| *item as i64).collect::<Vec<_>>();
-        let token_type_ids = encoding.get_type_ids().iter().map(|item| *item as i64).collect::<Vec<_>>();
-
-        // Run inference
-        let sequence_length = input_ids.len();
-
-        let input_ids = ndarray::CowArray::from(&input_ids)
-            .into_shape((1, sequence_length))
-            .map_err(|_| SemanticError::ShapeError)?
-            .into_dyn();
-
-        let input_ids = ndarray::CowArray::from(&input_ids)
-            .into_shape((1, sequence_length))
-            .map_err(|_| SemanticError::ShapeError)?
-            .into_dyn();
-        let input_ids = ort::Value::from_array(&input_ids).unwrap();
-
-        let attention_mask = ndarray::CowArray::from(&attention_mask)
-            .into_shape((1, sequence_length))
-            .map_err(|_| SemanticError::ShapeError)?
-            .into_dyn();
-        let attention_mask = ort::Value::from_array(&attention_mask).unwrap();
-
-        let token_type_ids = ndarray::CowArray::from(&token_type_ids)
-            .into_shape((1, sequence_length))
-            .map_err(|_| SemanticError::ShapeError)?
-            .into_dyn();
-        let token_type_ids = ort::Value::from_array(&token_type_ids).unwrap();
-
-        let outputs = self.session
-            .run(ort::inputs![input_ids, attention_mask, token_type_ids].unwrap())
-            .unwrap();
+        let encoding = self.encode(sequence)?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("tokens", encoding.len());
+
+        self.embed_encoding(&encoding, self.config.pooling)
+    }
+
+    /// Like [`Semantic::embed`], but runs the inference on Tokio's blocking thread pool so it doesn't stall the
+    /// async executor. Needs the `tokio` feature and must be called from within a Tokio runtime.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::Semantic;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embedding = semantic.embed_async("Hello world!".to_string()).await.unwrap();
+    /// # });
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn embed_async(&self, sequence: String) -> Result<Embedding, SemanticError> {
+        let semantic = self.clone();
+
+        tokio::task::spawn_blocking(move || semantic.embed(&sequence))
+            .await
+            .map_err(|e| SemanticError::AsyncTaskFailed(e.to_string()))?
+    }
+
+    /// Like [`Semantic::embed`], but gives up with [`SemanticError::Timeout`] once `timeout` has passed. The embedding
+    /// is computed on a new thread, and as ORT can't cancel a run, a timed out inference keeps that thread busy until
+    /// it finishes, only the caller gets control back. Not available with the `wasm` feature.
+    ///
+    /// Example:
+    /// ```rust
+    /// use std::time::Duration;
+    /// use inference_core::Semantic;
+    ///
+    /// let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embedding = semantic.embed_with_timeout("Hello world!", Duration::from_secs(5)).unwrap();
+    /// ```
+    #[cfg(not(feature = "wasm"))]
+    pub fn embed_with_timeout(&self, sequence: &str, timeout: std::time::Duration) -> Result<Embedding, SemanticError> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let semantic = self.clone();
+        let sequence = sequence.to_string();
+
+        std::thread::spawn(move || {
+            // the caller may have stopped waiting, then the result is dropped
+            let _ = sender.send(semantic.embed(&sequence));
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Err(SemanticError::Timeout(timeout)),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                Err(SemanticError::AsyncTaskFailed("the inference thread panicked".to_string()))
+            }
+        }
+    }
+
+    /// Embeds a search query, prepending the configured [`SemanticConfig::query_prefix`].
+    ///
+    /// Instruction-tuned models such as E5 and GTE are trained with these prefixes, and retrieval quality drops
+    /// sharply when they're missing or when queries and passages are embedded with the wrong one. Pair this with
+    /// [`Semantic::embed_passage`] for the documents being searched.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::SemanticBuilder;
+    ///
+    /// let model = std::fs::read("../model/model.onnx").unwrap();
+    /// let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+    ///
+    /// let semantic = SemanticBuilder::new(model, tokenizer_data)
+    ///     .query_prefix("query: ")
+    ///     .passage_prefix("passage: ")
+    ///     .build()
+    ///     .unwrap();
+    /// let query = semantic.embed_query("how do I embed text?").unwrap();
+    /// let passage = semantic.embed_passage("Call embed with the text to embed.").unwrap();
+    /// ```
+    pub fn embed_query(&self, text: &str) -> Result<Embedding, SemanticError> {
+        self.embed(&format!("{}{}", self.config.query_prefix, text))
+    }
+
+    /// Embeds a document to be searched, prepending the configured [`SemanticConfig::passage_prefix`].
+    /// See [`Semantic::embed_query`].
+    pub fn embed_passage(&self, text: &str) -> Result<Embedding, SemanticError> {
+        self.embed(&format!("{}{}", self.config.passage_prefix, text))
+    }
+
+    /// Embeds a sequence of text like [`Semantic::embed`], but pools the token embeddings with the given strategy
+    /// instead of the configured one.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, PoolingStrategy, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embedding = semantic.embed_with("Hello world!", PoolingStrategy::Cls).unwrap();
+    /// ```
+    pub fn embed_with(&self, sequence: &str, pooling: PoolingStrategy) -> Result<Embedding, SemanticError> {
+        let encoding = self.encode(sequence)?;
+
+        self.embed_encoding(&encoding, pooling)
+    }
+
+    /// Embeds a sequence of text like [`Semantic::embed`], with the options set in `options` overriding the ones this
+    /// [`Semantic`] was built with for this call only.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, EmbedOptions, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let options = EmbedOptions { normalize: Some(true), prefix: Some("query: ".to_string()), ..Default::default() };
+    /// let embedding = semantic.embed_with_options("Hello world!", &options).unwrap();
+    /// ```
+    pub fn embed_with_options(&self, sequence: &str, options: &EmbedOptions) -> Result<Embedding, SemanticError> {
+        ensure_not_blank(sequence)?;
+        let prefixed;
+        let sequence = match &options.prefix {
+            Some(prefix) => {
+                prefixed = format!("{}{}", prefix, sequence);
+                prefixed.as_str()
+            }
+            None => sequence,
+        };
+
+        let encoding = match options.max_length {
+            Some(max_length) => self.encode_within(sequence, max_length)?,
+            None => self.encode(sequence)?,
+        };
+
+        let mut out = Vec::new();
+        self.embed_encoding_into(
+            &encoding,
+            options.pooling.unwrap_or(self.config.pooling),
+            options.normalize.unwrap_or(self.config.normalize),
+            &mut out,
+        )?;
+
+        Ok(Embedding(out))
+    }
+
+    /// Embeds a JSON record by rendering it into `template` first, every `{field}` placeholder is replaced with that
+    /// field of the object. Fails with [`SemanticError::MissingTemplateField`] when a field is missing or `null`.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::init_semantic_with_path;
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let record = serde_json::json!({ "title": "Rust", "body": "A language empowering everyone" });
+    /// let embedding = semantic.embed_json(&record, "{title}. {body}").unwrap();
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn embed_json(&self, value: &serde_json::Value, template: &str) -> Result<Embedding, SemanticError> {
+        let text = crate::embedding::template::render(value, template)?;
+
+        self.embed(&text)
+    }
+
+    /// Embeds a sequence of text once per pooling strategy, running the model only once and pooling the same token
+    /// embeddings with each strategy. The embeddings are in the order of `poolings`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, PoolingStrategy, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embeddings = semantic.embed_multi("Hello world!", &[PoolingStrategy::Cls, PoolingStrategy::Mean]).unwrap();
+    /// assert_eq!(embeddings.len(), 2);
+    /// ```
+    pub fn embed_multi(&self, sequence: &str, poolings: &[PoolingStrategy]) -> Result<Vec<Embedding>, SemanticError> {
+        let encoding = self.encode(sequence)?;
+        if poolings.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let scoped_mask = self.scoped_mask(&[&encoding], encoding.len()).map_err(SemanticError::ShapeError)?;
+
+        self.with_token_embeddings(&encoding, |output, mask| {
+            let mask = match &scoped_mask {
+                Some(scoped) => scoped.view(),
+                None => mask.view(),
+            };
+
+            poolings.iter()
+                .map(|pooling| self.finish(Embedding(output.pool(*pooling, mask).row(0).to_vec())))
+                .collect()
+        })
+    }
+
+    /// Embeds a sequence of text like [`Semantic::embed`], and also reports how many tokens it produced
+    /// and whether it had to be truncated to fit the configured max length.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let output = semantic.embed_detailed("Hello world!").unwrap();
+    /// assert!(!output.truncated);
+    /// ```
+    pub fn embed_detailed(&self, sequence: &str) -> Result<EmbedOutput, SemanticError> {
+        let encoding = self.encode(sequence)?;
+        let embedding = self.embed_encoding(&encoding, self.config.pooling)?;
+
+        Ok(EmbedOutput {
+            embedding,
+            token_count: encoding.len(),
+            truncated: !encoding.get_overflowing().is_empty(),
+        })
+    }
+
+    /// Embeds a sequence of text like [`Semantic::embed`], but writes the result into `out` instead of allocating
+    /// a new vector. `out` is cleared and resized to [`Semantic::embedding_dim`] on every call, so reusing one buffer
+    /// across calls avoids the per-call allocation of the output.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let mut out = Vec::new();
+    /// for sequence in ["Hello world!", "Goodbye world!"] {
+    ///     semantic.embed_to(sequence, &mut out).unwrap();
+    /// }
+    /// ```
+    pub fn embed_to(&self, sequence: &str, out: &mut Vec<f32>) -> Result<(), SemanticError> {
+        let encoding = self.encode(sequence)?;
+
+        self.embed_encoding_into(&encoding, self.config.pooling, self.config.normalize, out)
+    }
+
+    /// Embeds a sequence of text like [`Semantic::embed`], but returns the pooled vector as the `ndarray` array it
+    /// is pooled into, without copying it into an [`Embedding`]. Needs the `ndarray-output` feature.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embedding = semantic.embed_array("Hello world!").unwrap();
+    /// assert_eq!(embedding.len(), semantic.embedding_dim());
+    /// ```
+    #[cfg(feature = "ndarray-output")]
+    pub fn embed_array(&self, sequence: &str) -> Result<ndarray::Array1<f32>, SemanticError> {
+        let encoding = self.encode(sequence)?;
+        let scoped_mask = self.scoped_mask(&[&encoding], encoding.len()).map_err(SemanticError::ShapeError)?;
+
+        self.with_token_embeddings(&encoding, |output, mask| {
+            let mask = match &scoped_mask {
+                Some(scoped) => scoped.view(),
+                None => mask.view(),
+            };
+            // a single pooled row is laid out the same in any memory order, so its raw data is the row itself
+            let mut values = output.pool(self.config.pooling, mask).into_raw_vec();
+            self.post_process(&mut values, self.config.normalize);
+
+            ndarray::Array1::from(values)
+        })
+    }
+
+    /// Embeds ids tokenized elsewhere, skipping the tokenizer and running the model directly. Without an attention
+    /// mask every token is attended to, and token types are always `0`. The ids must come from the tokenizer the
+    /// model was trained with, including its special tokens, as they are not checked against the vocabulary.
+    ///
+    /// The ids are pooled with the configured strategy over every attended token, the pooling scope is ignored as
+    /// it needs the tokenizer to tell special tokens apart. Fails with [`SemanticError::EmptyInput`] without ids,
+    /// and with [`SemanticError::DimensionMismatch`] when the mask isn't as long as the ids.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embedding = semantic.embed_ids(&[101, 7592, 2088, 102], None).unwrap();
+    /// assert_eq!(embedding.len(), semantic.embedding_dim());
+    /// ```
+    pub fn embed_ids(&self, input_ids: &[i64], attention_mask: Option<&[i64]>) -> Result<Embedding, SemanticError> {
+        if input_ids.is_empty() {
+            return Err(SemanticError::EmptyInput);
+        }
+        if let Some(mask) = attention_mask.filter(|mask| mask.len() != input_ids.len()) {
+            return Err(SemanticError::DimensionMismatch { expected: input_ids.len(), actual: mask.len() });
+        }
+        self.check_length(input_ids.len())?;
+
+        let mut buffers = self.buffers.take();
+        buffers.fill_ids(input_ids, attention_mask);
+
+        let result = self.run_buffers(&buffers, |output, mask| {
+            let mut values = output.pool(self.config.pooling, mask).row(0).to_vec();
+            self.post_process(&mut values, self.config.normalize);
+
+            Embedding(values)
+        });
+        self.buffers.give_back(buffers);
+
+        result
+    }
+
+    /// The batch counterpart of [`Semantic::embed_ids`], padding the sequences to the longest one and running them
+    /// through the model at once. Padding uses the tokenizer's pad token and is left out of pooling, like
+    /// [`Semantic::embed_batch`] does. The embeddings are in the order of `batch`.
+    ///
+    /// Without masks every id is attended to. Fails with [`SemanticError::EmptyInput`] when a sequence has no ids,
+    /// and with [`SemanticError::DimensionMismatch`] when there isn't one mask per sequence, each as long as its ids.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embeddings = semantic.embed_ids_batch(&[vec![101, 7592, 2088, 102], vec![101, 7592, 102]], None).unwrap();
+    /// assert_eq!(embeddings.len(), 2);
+    /// ```
+    pub fn embed_ids_batch(&self, batch: &[Vec<i64>], masks: Option<&[Vec<i64>]>) -> Result<Vec<Embedding>, SemanticError> {
+        if batch.is_empty() {
+            return Ok(vec![]);
+        }
+        self.ensure_bi_encoder()?;
+        if let Some(masks) = masks.filter(|masks| masks.len() != batch.len()) {
+            return Err(SemanticError::DimensionMismatch { expected: batch.len(), actual: masks.len() });
+        }
+        for (index, input_ids) in batch.iter().enumerate() {
+            if input_ids.is_empty() {
+                return Err(SemanticError::EmptyInput);
+            }
+            if let Some(mask) = masks.map(|masks| &masks[index]).filter(|mask| mask.len() != input_ids.len()) {
+                return Err(SemanticError::DimensionMismatch { expected: input_ids.len(), actual: mask.len() });
+            }
+            self.check_length(input_ids.len())?;
+        }
+
+        let batch_size = batch.len();
+        let sequence_length = batch.iter().map(Vec::len).max().unwrap_or(0);
+        let needs_padding = batch.iter().any(|input_ids| input_ids.len() < sequence_length);
+        let (pad_id, _) = if needs_padding { self.pad_ids()? } else { (0, 0) };
+        let side = self.config.padding_side;
+
+        let mut input_ids = Vec::with_capacity(batch_size * sequence_length);
+        let mut attention_mask = Vec::with_capacity(batch_size * sequence_length);
+        for (index, ids) in batch.iter().enumerate() {
+            let padding = sequence_length - ids.len();
+
+            side.extend_padded(&mut input_ids, ids.iter().copied(), padding, pad_id);
+            match masks {
+                Some(masks) => side.extend_padded(&mut attention_mask, masks[index].iter().copied(), padding, 0),
+                None => side.extend_padded(&mut attention_mask, std::iter::repeat_n(1, ids.len()), padding, 0),
+            }
+        }
+        let token_type_ids = vec![0; batch_size * sequence_length];
+
+        let shape = (batch_size, sequence_length);
+        let mask = ArrayView2::from_shape(shape, &attention_mask).map_err(SemanticError::ShapeError)?;
+
+        let outputs = self.run(
+            to_input_tensor(&input_ids, shape)?,
+            to_input_tensor(&attention_mask, shape)?,
+            to_input_tensor(&token_type_ids, shape)?,
+        )?;
+
+        self.output.element.with_f32(&outputs[self.output.name.as_str()], |output| {
+            let pooled = OutputEmbeddings::resolve(output, shape)?.pool(self.config.pooling, mask);
+
+            Ok(pooled.outer_iter().map(|row| self.finish(Embedding(row.to_vec()))).collect())
+        })?
+    }
+
+    /// Scores how relevant `doc` is to `query` with a cross-encoder, which reads both at once instead of embedding each
+    /// of them, for reranking the matches of a bi-encoder. The pair is encoded as the tokenizer's pair template, such as
+    /// `[CLS] query [SEP] doc [SEP]` with the doc's token types set to `1`, and truncating the longer of the two first.
+    /// The score is the model's raw logit, higher meaning more relevant, without any normalization or sigmoid.
+    ///
+    /// Fails with [`SemanticError::WrongModelKind`] when the model is a bi-encoder, see [`Semantic::model_kind`], and
+    /// with [`SemanticError::EmptyInput`] when either text is blank.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::Semantic;
+    ///
+    /// let reranker = Semantic::from_files("ms-marco-MiniLM-L-6-v2.onnx", "tokenizer.json").unwrap();
+    /// let relevant = reranker.score_pair("How many people live in Berlin?", "Berlin has 3.7 million inhabitants.").unwrap();
+    /// let unrelated = reranker.score_pair("How many people live in Berlin?", "New York is famous for its pizza.").unwrap();
+    /// assert!(relevant > unrelated);
+    /// ```
+    pub fn score_pair(&self, query: &str, doc: &str) -> Result<f32, SemanticError> {
+        if self.output.kind != ModelKind::CrossEncoder {
+            return Err(SemanticError::WrongModelKind(self.output.kind));
+        }
+        ensure_not_blank(query)?;
+        ensure_not_blank(doc)?;
+        let query = normalize_text(query, &self.config.text_normalizers)?;
+        let doc = normalize_text(doc, &self.config.text_normalizers)?;
+
+        let encoding = self.tokenizer.encode((query.as_ref(), doc.as_ref()), self.config.add_special_tokens)
+            .map_err(SemanticError::TokenizeEncodeError)?;
+        self.check_length(encoding.len())?;
+
+        let mut buffers = self.buffers.take();
+        buffers.fill(&encoding);
+        let shape = (1, buffers.input_ids.len());
+
+        let result = self.run(
+            to_input_tensor(&buffers.input_ids, shape)?,
+            to_input_tensor(&buffers.attention_mask, shape)?,
+            to_input_tensor(&buffers.token_type_ids, shape)?,
+        ).and_then(|outputs| self.output.element.with_f32(&outputs[self.output.name.as_str()], pair_score)?);
+        self.buffers.give_back(buffers);
+
+        result
+    }
+
+    /// Fails with [`SemanticError::WrongModelKind`] unless the model produces embeddings.
+    fn ensure_bi_encoder(&self) -> Result<(), SemanticError> {
+        match self.output.kind {
+            ModelKind::BiEncoder => Ok(()),
+            kind => Err(SemanticError::WrongModelKind(kind)),
+        }
+    }
+
+    fn embed_encoding(&self, encoding: &Encoding, pooling: PoolingStrategy) -> Result<Embedding, SemanticError> {
+        let mut out = Vec::new();
+        self.embed_encoding_into(encoding, pooling, self.config.normalize, &mut out)?;
+
+        Ok(Embedding(out))
+    }
+
+    /// Returns the model's embedding of every token of the sequence, paired with the token it belongs to, before
+    /// any pooling or normalization. Special tokens the tokenizer adds, such as `[CLS]` and `[SEP]`, are included, see
+    /// [`Semantic::embed_tokens_with`] to leave them out.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let tokens = semantic.embed_tokens("Hello world!").unwrap();
+    /// assert_eq!(tokens.first().unwrap().0, "[CLS]");
+    /// ```
+    pub fn embed_tokens(&self, sequence: &str) -> Result<Vec<(String, Embedding)>, SemanticError> {
+        self.embed_tokens_with(sequence, false)
+    }
+
+    /// Like [`Semantic::embed_tokens`], leaving out the special tokens when `skip_special_tokens` is set, as marked
+    /// by the encoding's special tokens mask. Padding is never returned. Either way every embedding stays paired with
+    /// the token at its own position, in the order of the sequence, so skipping only drops pairs and never shifts them.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let tokens = semantic.embed_tokens_with("Hello world!", true).unwrap();
+    /// assert_eq!(tokens.first().unwrap().0, "hello");
+    /// ```
+    pub fn embed_tokens_with(&self, sequence: &str, skip_special_tokens: bool) -> Result<Vec<(String, Embedding)>, SemanticError> {
+        let encoding = self.encode(sequence)?;
+        let special_tokens = encoding.get_special_tokens_mask();
+
+        self.with_token_embeddings(&encoding, |output, mask| {
+            let token_embeddings = output.tokens()?;
+
+            Ok(encoding.get_tokens().iter()
+                .enumerate()
+                .filter(|(index, _)| mask[[0, *index]] != 0)
+                .filter(|(index, _)| !skip_special_tokens || special_tokens[*index] == 0)
+                .map(|(index, token)| (token.clone(), Embedding(token_embeddings.slice(ndarray::s![0, index, ..]).to_vec())))
+                .collect())
+        })?
+    }
+
+    fn embed_encoding_into(&self, encoding: &Encoding, pooling: PoolingStrategy, normalize: bool, out: &mut Vec<f32>) -> Result<(), SemanticError> {
+        let scoped_mask = self.scoped_mask(&[encoding], encoding.len()).map_err(SemanticError::ShapeError)?;
+
+        self.with_token_embeddings(encoding, |output, mask| {
+            let pooled = match &scoped_mask {
+                Some(scoped) => output.pool(pooling, scoped.view()),
+                None => output.pool(pooling, mask),
+            };
+
+            out.clear();
+            out.extend(pooled.row(0).iter());
+            self.post_process(out, normalize);
+        })
+    }
+
+    /// Runs the model on a single encoding and hands its output, usually `(1, seq_len, hidden)` token embeddings, and
+    /// attention mask to `f`.
+    fn with_token_embeddings<R>(&self, encoding: &Encoding, f: impl FnOnce(OutputEmbeddings, ArrayView2<i64>) -> R) -> Result<R, SemanticError> {
+        let mut buffers = self.buffers.take();
+        buffers.fill(encoding);
+
+        let result = self.run_buffers(&buffers, f);
+        self.buffers.give_back(buffers);
+
+        result
+    }
+
+    fn run_buffers<R>(&self, buffers: &InputBuffers, f: impl FnOnce(OutputEmbeddings, ArrayView2<i64>) -> R) -> Result<R, SemanticError> {
+        self.ensure_bi_encoder()?;
+        let shape = (1, buffers.input_ids.len());
+
+        let mask = ArrayView2::from_shape(shape, &buffers.attention_mask)
+            .map_err(SemanticError::ShapeError)?;
 
-        let output_tensor = outputs[0].extract_tensor::<f32>().unwrap();
-        let sequence_embedding = &*output_tensor.view();
-        let pooled = sequence_embedding.mean_axis(Axis(1)).unwrap();
+        let outputs = self.run(
+            to_input_tensor(&buffers.input_ids, shape)?,
+            to_input_tensor(&buffers.attention_mask, shape)?,
+            to_input_tensor(&buffers.token_type_ids, shape)?,
+        )?;
 
-        Ok(Embedding(pooled.to_owned().as_slice().unwrap().to_vec()))
+        self.output.element.with_f32(&outputs[self.output.name.as_str()], |output| {
+            Ok(f(OutputEmbeddings::resolve(output, shape)?, mask.view()))
+        })?
     }
+
+    /// Embeds a batch of sequences like [`Semantic::embed_batch`], but stacks the embeddings into one contiguous
+    /// `(sequences, embedding_dim)` matrix, row `i` being the embedding of `sequences[i]`. An empty batch has no
+    /// rows to stack and fails with [`SemanticError::EmptyInput`]. Needs the `ndarray-output` feature.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let matrix = semantic.embed_matrix(&["Hello world!", "A much longer sentence to embed"]).unwrap();
+    /// assert_eq!(matrix.dim(), (2, semantic.embedding_dim()));
+    /// ```
+    #[cfg(feature = "ndarray-output")]
+    pub fn embed_matrix(&self, sequences: &[&str]) -> Result<ndarray::Array2<f32>, SemanticError> {
+        if sequences.is_empty() {
+            return Err(SemanticError::EmptyInput);
+        }
+
+        let embeddings = self.embed_batch(sequences)?;
+        let dim = embeddings[0].len();
+        let mut values = Vec::with_capacity(embeddings.len() * dim);
+        for embedding in &embeddings {
+            if embedding.len() != dim {
+                return Err(SemanticError::DimensionMismatch { expected: dim, actual: embedding.len() });
+            }
+            values.extend_from_slice(embedding.as_slice());
+        }
+
+        ndarray::Array2::from_shape_vec((embeddings.len(), dim), values).map_err(SemanticError::ShapeError)
+    }
+
+    /// Embeds a batch of sequences in a single forward pass. Sequences are padded to the longest one in the batch,
+    /// and the attention mask makes sure padded positions are ignored when pooling.
+    ///
+    /// With [`SemanticConfig::max_batch_tokens`] set, sequences are instead sorted by length and split into
+    /// sub-batches of similar length, so one long outlier doesn't pad the whole batch. Embeddings are always
+    /// returned in the order of `sequences`.
+    ///
+    /// With the `tracing` feature, every call is recorded in an `embed_batch` span with the number of sequences, their
+    /// total token count and the longest sequence length.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embeddings = semantic.embed_batch(&["Hello world!", "A much longer sentence to embed"]).unwrap();
+    /// assert_eq!(embeddings.len(), 2);
+    /// ```
+    pub fn embed_batch(&self, sequences: &[&str]) -> Result<Vec<Embedding>, SemanticError> {
+        self.embed_batch_with_progress(sequences, |_, _| {})
+    }
+
+    /// Embeds a batch of sequences like [`Semantic::embed_batch`], calling `progress` with the number of sequences
+    /// embedded so far and the size of the batch after every forward pass, e.g. to update a progress bar. That is
+    /// once per sub-batch with [`SemanticConfig::max_batch_tokens`] set and once for the whole batch otherwise, and
+    /// never for an empty batch. The last call reports every sequence as processed.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let embeddings = semantic.embed_batch_with_progress(&["Hello world!", "How are you?"], |processed, total| {
+    ///     println!("{}/{}", processed, total);
+    /// }).unwrap();
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "embed_batch", level = "debug", skip_all, fields(sequences = sequences.len(), tokens, sequence_length)))]
+    pub fn embed_batch_with_progress(&self, sequences: &[&str], mut progress: impl FnMut(usize, usize)) -> Result<Vec<Embedding>, SemanticError> {
+        if sequences.is_empty() {
+            return Ok(vec![]);
+        }
+
+        for sequence in sequences {
+            ensure_not_blank(sequence)?;
+        }
+
+        let sequences = sequences.iter()
+            .map(|sequence| normalize_text(sequence, &self.config.text_normalizers))
+            .collect::<Result<Vec<_>, _>>()?;
+        let encodings = self.tokenizer.encode_batch(sequences.iter().map(|sequence| sequence.as_ref()).collect(), self.config.add_special_tokens)
+            .map_err(SemanticError::TokenizeEncodeError)?;
+        for encoding in &encodings {
+            self.check_length(encoding.len())?;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::Span::current()
+            .record("tokens", encodings.iter().map(|encoding| encoding.len()).sum::<usize>())
+            .record("sequence_length", encodings.iter().map(|encoding| encoding.len()).max().unwrap_or(0));
+
+        let total = encodings.len();
+        let budget = match self.config.max_batch_tokens {
+            Some(budget) => budget,
+            None => {
+                let embeddings = self.embed_padded(&encodings.iter().collect::<Vec<_>>())?;
+                progress(total, total);
+                return Ok(embeddings);
+            }
+        };
+
+        let lengths = encodings.iter().map(|encoding| encoding.len()).collect::<Vec<_>>();
+        let mut embeddings: Vec<Option<Embedding>> = vec![None; total];
+        let mut processed = 0;
+        for sub_batch in plan_sub_batches(&lengths, budget) {
+            let batch = sub_batch.iter().map(|index| &encodings[*index]).collect::<Vec<_>>();
+            processed += batch.len();
+            for (index, embedding) in sub_batch.into_iter().zip(self.embed_padded(&batch)?) {
+                embeddings[index] = Some(embedding);
+            }
+            progress(processed, total);
+        }
+
+        Ok(embeddings.into_iter()
+            .map(|embedding| embedding.expect("every input belongs to one sub-batch"))
+            .collect())
+    }
+
+    /// Embeds the encodings in one forward pass, padded to the longest of them.
+    fn embed_padded(&self, encodings: &[&Encoding]) -> Result<Vec<Embedding>, SemanticError> {
+        let batch_size = encodings.len();
+        let sequence_length = encodings.iter().map(|encoding| encoding.len()).max().unwrap_or(0);
+        let needs_padding = encodings.iter().any(|encoding| encoding.len() < sequence_length);
+        let (pad_id, pad_type_id) = if needs_padding { self.pad_ids()? } else { (0, 0) };
+        let side = self.config.padding_side;
+
+        let mut input_ids = Vec::with_capacity(batch_size * sequence_length);
+        let mut attention_mask = Vec::with_capacity(batch_size * sequence_length);
+        let mut token_type_ids = Vec::with_capacity(batch_size * sequence_length);
+
+        for encoding in encodings {
+            let padding = sequence_length - encoding.len();
+
+            side.extend_padded(&mut input_ids, encoding.get_ids().iter().map(|item| *item as i64), padding, pad_id);
+            side.extend_padded(&mut attention_mask, encoding.get_attention_mask().iter().map(|item| *item as i64), padding, 0);
+            side.extend_padded(&mut token_type_ids, encoding.get_type_ids().iter().map(|item| *item as i64), padding, pad_type_id);
+        }
+
+        let shape = (batch_size, sequence_length);
+        self.ensure_bi_encoder()?;
+
+        let scoped_mask = self.scoped_mask(encodings, sequence_length).map_err(SemanticError::ShapeError)?;
+        let mask = match &scoped_mask {
+            Some(scoped) => scoped.view(),
+            None => ArrayView2::from_shape(shape, &attention_mask).map_err(SemanticError::ShapeError)?,
+        };
+
+        let outputs = self.run(
+            to_input_tensor(&input_ids, shape)?,
+            to_input_tensor(&attention_mask, shape)?,
+            to_input_tensor(&token_type_ids, shape)?,
+        )?;
+
+        self.output.element.with_f32(&outputs[self.output.name.as_str()], |output| {
+            let pooled = OutputEmbeddings::resolve(output, shape)?.pool(self.config.pooling, mask.view());
+
+            Ok(pooled.outer_iter().map(|row| self.finish(Embedding(row.to_vec()))).collect())
+        })?
+    }
+
+    /// Splits the text into sentences and embeds each of them, returning every sentence paired with its embedding.
+    /// Sentences end at `.`, `!` or `?` followed by whitespace, text without any is embedded as one sentence,
+    /// and blank text returns nothing.
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let sentences = semantic.embed_sentences("Rust is fast. It is also safe.").unwrap();
+    /// assert_eq!(sentences[1].0, "It is also safe.");
+    /// ```
+    pub fn embed_sentences(&self, text: &str) -> Result<Vec<(String, Embedding)>, SemanticError> {
+        let sentences = split_sentences(text);
+        let embeddings = self.embed_batch(&sentences)?;
+
+        Ok(sentences.into_iter().map(str::to_string).zip(embeddings).collect())
+    }
+
+    /// Embeds a text longer than the model context by splitting it into windows of `window` tokens, each overlapping
+    /// the previous one by `overlap` tokens, and averaging the window embeddings. Windows break on token boundaries.
+    ///
+    /// The window counts tokens without the special tokens every window is embedded with, so it can be at most the
+    /// max length minus those, e.g. 510 for BERT at the default max length of 512. Longer windows, and an `overlap`
+    /// not shorter than the window, fail with [`SemanticError::InvalidWindow`].
+    ///
+    /// Example:
+    /// ```rust
+    /// use inference_core::{init_semantic_with_path, Semantic};
+    ///
+    /// let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+    /// let document = "A very long document. ".repeat(500);
+    /// let embedding = semantic.embed_long(&document, 256, 32).unwrap();
+    /// ```
+    pub fn embed_long(&self, text: &str, window: usize, overlap: usize) -> Result<Embedding, SemanticError> {
+        // a longer window would be truncated, or rejected without truncation, once its special tokens are added
+        let max_window = self.config.max_length.saturating_sub(self.special_token_count());
+        if window == 0 || overlap >= window || window > max_window {
+            return Err(SemanticError::InvalidWindow { window, overlap });
+        }
+        ensure_not_blank(text)?;
+        // the windows are cut from the normalized text, which the offsets point into
+        let text = &*normalize_text(text, &self.config.text_normalizers)?;
+
+        let encoding = self.tokenizer.encode(text, false)
+            .map_err(SemanticError::TokenizeEncodeError)?;
+        // with truncation enabled, the tail of the text ends up in the overflowing encodings
+        let offsets: Vec<(usize, usize)> = std::iter::once(&encoding)
+            .chain(encoding.get_overflowing())
+            .flat_map(|encoding| encoding.get_offsets().iter().copied())
+            .collect();
+
+        if offsets.is_empty() {
+            return self.embed(text);
+        }
+
+        let mut chunks = vec![];
+        let mut start = 0;
+        loop {
+            let end = (start + window).min(offsets.len());
+            let (from, to) = (offsets[start].0, offsets[end - 1].1);
+            chunks.push(&text[from..to.max(from)]);
+
+            if end == offsets.len() {
+                break;
+            }
+            start += window - overlap;
+        }
+
+        let embeddings = self.embed_batch(&chunks)?;
+
+        let mut summed = vec![0.0; self.embedding_dim];
+        for embedding in &embeddings {
+            for (sum, value) in summed.iter_mut().zip(embedding.iter()) {
+                *sum += value;
+            }
+        }
+
+        let count = embeddings.len() as f32;
+        Ok(self.finish(Embedding(summed.into_iter().map(|sum| sum / count).collect())))
+    }
+
+    /// Runs the session, binding each tensor to the input name the model declares for it.
+    /// With the `tracing` feature, the forward pass gets its own `inference` span, which records how long it took.
+    fn run(&self, input_ids: ort::Value, attention_mask: ort::Value, token_type_ids: ort::Value) -> Result<ort::SessionOutputs<'_>, SemanticError> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("inference", duration_us = tracing::field::Empty).entered();
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let inputs = self.inputs.bind(input_ids, attention_mask, token_type_ids);
+        let outputs = self.session.run(inputs)
+            .map_err(SemanticError::Inference);
+
+        #[cfg(feature = "tracing")]
+        span.record("duration_us", started.elapsed().as_micros() as u64);
+
+        outputs
+    }
+
+    /// The `(batch, sequence)` mask of the tokens to pool, padded like the attention mask, or `None` when every
+    /// attended token is pooled.
+    fn scoped_mask(&self, encodings: &[&Encoding], sequence_length: usize) -> Result<Option<ndarray::Array2<i64>>, ndarray::ShapeError> {
+        let scope = self.config.pooling_scope;
+        if scope == PoolingScope::All {
+            return Ok(None);
+        }
+
+        let mut mask = Vec::with_capacity(encodings.len() * sequence_length);
+        for encoding in encodings {
+            let scoped = scope.mask(encoding.get_attention_mask(), encoding.get_special_tokens_mask());
+            self.config.padding_side.extend_padded(&mut mask, scoped, sequence_length - encoding.len(), 0);
+        }
+
+        ndarray::Array2::from_shape_vec((encodings.len(), sequence_length), mask).map(Some)
+    }
+
+    /// Applies the configured post-processing to a freshly pooled embedding.
+    fn finish(&self, embedding: Embedding) -> Embedding {
+        let mut values = embedding.into_vec();
+        self.post_process(&mut values, self.config.normalize);
+
+        Embedding(values)
+    }
+
+    /// Truncates the pooled values to the output dimension, then normalizes what is left.
+    fn post_process(&self, values: &mut Vec<f32>, normalize: bool) {
+        if let Some(dim) = self.config.output_dim {
+            values.truncate(dim);
+        }
+        if normalize {
+            normalize_in_place(values);
+        }
+    }
+
+    /// Encodes like [`Semantic::encode`], within a max length lower than the one the tokenizer truncates to. The
+    /// sequence is truncated without special tokens, which are added back afterwards, as the tokenizer does itself.
+    fn encode_within(&self, sequence: &str, max_length: usize) -> Result<Encoding, SemanticError> {
+        if max_length >= self.config.max_length {
+            return self.encode(sequence);
+        }
+        ensure_not_blank(sequence)?;
+        let sequence = normalize_text(sequence, &self.config.text_normalizers)?;
+
+        let special_tokens = self.special_token_count();
+        let mut encoding = self.tokenizer.encode(sequence.as_ref(), false)
+            .map_err(SemanticError::TokenizeEncodeError)?;
+        let length = encoding.len() + special_tokens;
+        if length > max_length {
+            if !self.config.truncate {
+                return Err(SemanticError::InputTooLong { length, max_length });
+            }
+            encoding.truncate(max_length.saturating_sub(special_tokens), 0, TruncationDirection::Right);
+        }
+
+        let encoding = self.tokenizer.post_process(encoding, None, self.config.add_special_tokens)
+            .map_err(SemanticError::TokenizeEncodeError)?;
+        self.check_length(encoding.len())?;
+
+        Ok(encoding)
+    }
+
+    /// The number of special tokens the tokenizer adds to a single sequence, such as `[CLS]` and `[SEP]`.
+    fn special_token_count(&self) -> usize {
+        match self.tokenizer.get_post_processor() {
+            Some(processor) if self.config.add_special_tokens => processor.added_tokens(false),
+            _ => 0,
+        }
+    }
+
+    fn encode(&self, sequence: &str) -> Result<Encoding, SemanticError> {
+        ensure_not_blank(sequence)?;
+        let sequence = normalize_text(sequence, &self.config.text_normalizers)?;
+
+        let encoding = self.tokenizer.encode(sequence.as_ref(), self.config.add_special_tokens)
+            .map_err(SemanticError::TokenizeEncodeError)?;
+        self.check_length(encoding.len())?;
+
+        Ok(encoding)
+    }
+
+    fn check_length(&self, length: usize) -> Result<(), SemanticError> {
+        if self.config.collect_stats {
+            self.lengths.record(length);
+        }
+        // without special tokens, text the normalizer strips entirely leaves nothing to pool
+        if length == 0 {
+            return Err(SemanticError::EmptyTokenization);
+        }
+        if length > self.config.max_length {
+            return Err(SemanticError::InputTooLong { length, max_length: self.config.max_length });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the pad token id and pad type id, see [`SemanticConfig::pad_token_id`].
+    fn pad_ids(&self) -> Result<(i64, i64), SemanticError> {
+        resolve_pad_ids(&self.tokenizer, self.config.pad_token_id).ok_or(SemanticError::NoPadToken)
+    }
+}
+
+/// The configured pad token id takes precedence over the tokenizer's padding, then over its `[PAD]` or `<pad>` token.
+/// The pad type id comes from the tokenizer's padding and defaults to `0`.
+fn resolve_pad_ids(tokenizer: &tokenizers::Tokenizer, pad_token_id: Option<u32>) -> Option<(i64, i64)> {
+    let padding = tokenizer.get_padding();
+    let pad_type_id = padding.map_or(0, |params| params.pad_type_id as i64);
+    let pad_id = pad_token_id
+        .or_else(|| padding.map(|params| params.pad_id))
+        .or_else(|| tokenizer.token_to_id("[PAD]"))
+        .or_else(|| tokenizer.token_to_id("<pad>"))?;
+
+    Some((pad_id as i64, pad_type_id))
+}
+
+/// Used where the session has to run once without any real input.
+const DUMMY_INPUT: &str = "hello";
+
+/// Shapes a flat row-major buffer into a `(batch, sequence)` model input.
+fn to_input_tensor(data: &[i64], shape: (usize, usize)) -> Result<ort::Value, SemanticError> {
+    let array = ndarray::CowArray::from(ArrayView2::from_shape(shape, data).map_err(SemanticError::ShapeError)?)
+        .into_dyn();
+
+    ort::Value::from_array(&array).map_err(SemanticError::ValueCreation)
+}
+
+/// Blank text tokenizes to nothing but special tokens, whose embedding carries no meaning.
+fn ensure_not_blank(sequence: &str) -> Result<(), SemanticError> {
+    if sequence.trim().is_empty() {
+        return Err(SemanticError::EmptyInput);
+    }
+
+    Ok(())
+}
+
+// keeps the thread-safety guarantee documented on `Semantic` from silently breaking
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Semantic>();
+};
+
+/// Whether the process-wide ORT environment has been committed.
+#[cfg(not(feature = "wasm"))]
+static ENVIRONMENT_READY: Mutex<bool> = Mutex::new(false);
+
+/// Commits the ORT environment once per process, later calls reuse it. A failed commit isn't remembered,
+/// so the next `Semantic` built tries again.
+#[cfg(not(feature = "wasm"))]
+fn init_environment(log_level: LogLevel) -> Result<(), SemanticError> {
+    let mut ready = ENVIRONMENT_READY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if *ready {
+        return Ok(());
+    }
+
+    ort::init()
+        .with_name("Encode")
+        .with_log_level(log_level.into())
+        .with_execution_providers([ExecutionProviderDispatch::CPU(Default::default())])
+        .commit()
+        .map_err(SemanticError::InitBuildOrtEnv)?;
+    *ready = true;
+
+    Ok(())
+}
+
+/// In the browser there is no native environment to commit, sessions use ORT's default one.
+#[cfg(feature = "wasm")]
+fn init_environment(_log_level: LogLevel) -> Result<(), SemanticError> {
+    Ok(())
 }
 
 type Result<T, E = SemanticError> = std::result::Result<T, E>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum SemanticError {
-    TokenizeEncodeError,
-    TokenizeEncodeByteError,
-    ShapeError,
-    InitSessionBuilder,
-    InitSessionOptimization,
-    InitBuildOrtEnv,
-    InitSessionThreads,
-    InitModelReadError,
-    InitTokenizerReadError,
+    #[error("TokenizeEncodeError: {0}")]
+    TokenizeEncodeError(#[source] tokenizers::Error),
+    #[error("TokenizeEncodeByteError: {0}")]
+    TokenizeEncodeByteError(#[source] tokenizers::Error),
+    #[error("ShapeError: {0}")]
+    ShapeError(#[source] ndarray::ShapeError),
+    #[error("InitSessionBuilder: {0}")]
+    InitSessionBuilder(#[source] ort::Error),
+    #[error("InitSessionOptimization: {0}")]
+    InitSessionOptimization(#[source] ort::Error),
+    #[error("InitBuildOrtEnv: {0}")]
+    InitBuildOrtEnv(#[source] ort::Error),
+    #[error("InitSessionThreads: {0}")]
+    InitSessionThreads(#[source] ort::Error),
+    #[error("InitSessionModel: {0}")]
+    InitSessionModel(#[source] ort::Error),
+    #[error("InitModelReadError: {0}")]
+    InitModelReadError(#[source] std::io::Error),
+    #[error("InitTokenizerReadError: {0}")]
+    InitTokenizerReadError(#[source] std::io::Error),
+    #[error("DimensionMismatch: expected {expected} dimensions, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    #[error("ValueCreation: {0}")]
+    ValueCreation(#[source] ort::Error),
+    #[error("Inference: {0}")]
+    Inference(#[source] ort::Error),
+    #[error("TensorExtract: {0}")]
+    TensorExtract(#[source] ort::Error),
+    #[error("PoolingFailed: {0}")]
+    PoolingFailed(#[source] ndarray::ShapeError),
+    #[error("InvalidThreadCount: thread count must be positive, got {0}")]
+    InvalidThreadCount(i16),
+    #[error("MissingModelInput: the model has no `{0}` input")]
+    MissingModelInput(String),
+    #[error("InitTokenizerTruncation: {0}")]
+    InitTokenizerTruncation(#[source] tokenizers::Error),
+    #[error("InputTooLong: {length} tokens exceed the maximum of {max_length}")]
+    InputTooLong { length: usize, max_length: usize },
+    #[error("InvalidWindow: a window of {window} tokens must be longer than its overlap ({overlap}) and fit the max length with its special tokens")]
+    InvalidWindow { window: usize, overlap: usize },
+    #[error("InvalidByteLength: {0} bytes is not a multiple of 4")]
+    InvalidByteLength(usize),
+    #[error("InvalidPoolSize: a pool needs at least one session, got {0}")]
+    InvalidPoolSize(usize),
+    #[error("UnsupportedOutputType: {0}")]
+    UnsupportedOutputType(String),
+    #[error("MissingModelOutput: the model has no output {0}")]
+    MissingModelOutput(String),
+    #[error("EmptyInput: cannot embed empty or whitespace-only text")]
+    EmptyInput,
+    #[error("AsyncTaskFailed: {0}")]
+    AsyncTaskFailed(String),
+    #[error("BundleReadError: {0}")]
+    BundleReadError(#[source] std::io::Error),
+    #[error("BundleMissingFile: the bundle has no {0}")]
+    BundleMissingFile(String),
+    #[error("InvalidEmbedding: {0}")]
+    InvalidEmbedding(String),
+    #[error("ModelTokenizerMismatch: the tokenizer has {vocab_size} tokens, but the model only embeds {embedding_rows}")]
+    ModelTokenizerMismatch { vocab_size: usize, embedding_rows: usize },
+    #[error("InvalidQuantizer: {0}")]
+    InvalidQuantizer(String),
+    #[error("Timeout: no embedding after {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("InvalidDimension: can't keep {dim} of {available} dimensions")]
+    InvalidDimension { dim: usize, available: usize },
+    #[error("UnexpectedOutputRank: can't pool a model output of rank {0}")]
+    UnexpectedOutputRank(usize),
+    #[error("NoPadToken: the tokenizer has no pad token to pad the batch with, set one with `pad_token_id`")]
+    NoPadToken,
+    #[error("EmptyTokenization: the text produced no tokens")]
+    EmptyTokenization,
+    #[error("MissingTemplateField: the record has no `{0}` field")]
+    MissingTemplateField(String),
+    #[error("FetchFailed: couldn't fetch {url}{}", .status.map(|status| format!(", the server responded with {}", status)).unwrap_or_default())]
+    FetchFailed { url: String, status: Option<u16> },
+    #[error("InvalidClusterCount: can't group embeddings into {0} clusters")]
+    InvalidClusterCount(usize),
+    #[error("NpyIoError: {0}")]
+    NpyIoError(#[source] std::io::Error),
+    #[error("InvalidNpy: {0}")]
+    InvalidNpy(String),
+    #[error("WrongModelKind: not supported by a {0:?} model, see Semantic::model_kind")]
+    WrongModelKind(ModelKind),
+    #[error("InvalidHashDimension: a hash embedding needs at least one dimension, got {0}")]
+    InvalidHashDimension(usize),
 }
 
-impl Display for SemanticError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl SemanticError {
+    /// A numeric code identifying the variant, for hosts on the other side of an FFI boundary that can't match on
+    /// the enum or shouldn't parse the message. The codes are stable: a variant keeps its code across releases, new
+    /// variants get the next unused one and the codes of removed variants are never reused.
+    pub fn code(&self) -> u32 {
         match self {
-            SemanticError::TokenizeEncodeError => write!(f, "TokenizeEncodeError"),
-            SemanticError::ShapeError => write!(f, "ShapeError"),
-            SemanticError::TokenizeEncodeByteError => write!(f, "TokenizeEncodeByteError"),
-            SemanticError::InitSessionBuilder => write!(f, "InitSessionBuilder"),
-            SemanticError::InitSessionOptimization => write!(f, "InitSessionOptimization"),
-            SemanticError::InitSessionThreads => write!(f, "InitSessionThreads"),
-            SemanticError::InitBuildOrtEnv => write!(f, "InitBuildOrtEnv"),
-            SemanticError::InitModelReadError => write!(f, "InitModelReadError"),
-            SemanticError::InitTokenizerReadError => write!(f, "InitTokenizerReadError"),
-        }
-    }
-}
\ No newline at end of file
+            SemanticError::TokenizeEncodeError(_) => 1,
+            SemanticError::TokenizeEncodeByteError(_) => 2,
+            SemanticError::ShapeError(_) => 3,
+            SemanticError::InitSessionBuilder(_) => 4,
+            SemanticError::InitSessionOptimization(_) => 5,
+            SemanticError::InitBuildOrtEnv(_) => 6,
+            SemanticError::InitSessionThreads(_) => 7,
+            SemanticError::InitSessionModel(_) => 8,
+            SemanticError::InitModelReadError(_) => 9,
+            SemanticError::InitTokenizerReadError(_) => 10,
+            SemanticError::DimensionMismatch { .. } => 11,
+            SemanticError::ValueCreation(_) => 12,
+            SemanticError::Inference(_) => 13,
+            SemanticError::TensorExtract(_) => 14,
+            SemanticError::PoolingFailed(_) => 15,
+            SemanticError::InvalidThreadCount(_) => 16,
+            SemanticError::MissingModelInput(_) => 17,
+            SemanticError::InitTokenizerTruncation(_) => 18,
+            SemanticError::InputTooLong { .. } => 19,
+            SemanticError::InvalidWindow { .. } => 20,
+            SemanticError::InvalidByteLength(_) => 21,
+            SemanticError::InvalidPoolSize(_) => 22,
+            SemanticError::UnsupportedOutputType(_) => 23,
+            SemanticError::MissingModelOutput(_) => 24,
+            SemanticError::EmptyInput => 25,
+            SemanticError::AsyncTaskFailed(_) => 26,
+            SemanticError::BundleReadError(_) => 27,
+            SemanticError::BundleMissingFile(_) => 28,
+            SemanticError::InvalidEmbedding(_) => 29,
+            SemanticError::ModelTokenizerMismatch { .. } => 30,
+            SemanticError::InvalidQuantizer(_) => 31,
+            SemanticError::Timeout(_) => 32,
+            SemanticError::InvalidDimension { .. } => 33,
+            SemanticError::UnexpectedOutputRank(_) => 34,
+            SemanticError::NoPadToken => 35,
+            SemanticError::EmptyTokenization => 36,
+            SemanticError::MissingTemplateField(_) => 37,
+            SemanticError::FetchFailed { .. } => 38,
+            SemanticError::InvalidClusterCount(_) => 39,
+            SemanticError::NpyIoError(_) => 40,
+            SemanticError::InvalidNpy(_) => 41,
+            SemanticError::WrongModelKind(_) => 42,
+            SemanticError::InvalidHashDimension(_) => 43,
+        }
+    }
+
+    /// Whether the same call may succeed when retried. Only failures of the inference run itself, timeouts, file
+    /// reads that were interrupted or timed out, and fetches that failed on the network or with a 5xx status are
+    /// transient, invalid input, missing or malformed models and configuration errors fail the same way every time.
+    /// A panicking async task is a bug rather than a transient failure.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SemanticError::Inference(_) | SemanticError::Timeout(_) => true,
+            SemanticError::InitModelReadError(e)
+            | SemanticError::InitTokenizerReadError(e)
+            | SemanticError::NpyIoError(e) => is_transient_io(e),
+            SemanticError::FetchFailed { status, .. } => status.is_none_or(|status| status >= 500),
+            SemanticError::TokenizeEncodeError(_)
+            | SemanticError::TokenizeEncodeByteError(_)
+            | SemanticError::ShapeError(_)
+            | SemanticError::InitSessionBuilder(_)
+            | SemanticError::InitSessionOptimization(_)
+            | SemanticError::InitBuildOrtEnv(_)
+            | SemanticError::InitSessionThreads(_)
+            | SemanticError::InitSessionModel(_)
+            | SemanticError::DimensionMismatch { .. }
+            | SemanticError::ValueCreation(_)
+            | SemanticError::TensorExtract(_)
+            | SemanticError::PoolingFailed(_)
+            | SemanticError::InvalidThreadCount(_)
+            | SemanticError::MissingModelInput(_)
+            | SemanticError::InitTokenizerTruncation(_)
+            | SemanticError::InputTooLong { .. }
+            | SemanticError::InvalidWindow { .. }
+            | SemanticError::InvalidByteLength(_)
+            | SemanticError::InvalidPoolSize(_)
+            | SemanticError::UnsupportedOutputType(_)
+            | SemanticError::MissingModelOutput(_)
+            | SemanticError::EmptyInput
+            | SemanticError::AsyncTaskFailed(_)
+            | SemanticError::BundleReadError(_)
+            | SemanticError::BundleMissingFile(_)
+            | SemanticError::InvalidEmbedding(_)
+            | SemanticError::ModelTokenizerMismatch { .. }
+            | SemanticError::InvalidDimension { .. }
+            | SemanticError::UnexpectedOutputRank(_)
+            | SemanticError::NoPadToken
+            | SemanticError::EmptyTokenization
+            | SemanticError::MissingTemplateField(_)
+            | SemanticError::InvalidClusterCount(_)
+            | SemanticError::InvalidNpy(_)
+            | SemanticError::WrongModelKind(_)
+            | SemanticError::InvalidHashDimension(_)
+            | SemanticError::InvalidQuantizer(_) => false,
+        }
+    }
+}
+
+fn is_transient_io(error: &std::io::Error) -> bool {
+    matches!(error.kind(), std::io::ErrorKind::Interrupted | std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_shape_input_tensor_row_major() {
+        let tensor = to_input_tensor(&[101, 7, 102, 101, 102, 0], (2, 3)).unwrap();
+
+        let extracted = tensor.extract_tensor::<i64>().unwrap();
+        assert_eq!(*extracted.view(), ndarray::array![[101, 7, 102], [101, 102, 0]].into_dyn());
+    }
+
+    #[test]
+    fn should_reject_buffer_not_matching_shape() {
+        assert!(matches!(to_input_tensor(&[101, 102], (1, 3)), Err(SemanticError::ShapeError(_))));
+    }
+
+    fn word_level_tokenizer(tokens: &[&str]) -> tokenizers::Tokenizer {
+        let vocab = tokens.iter().enumerate().map(|(id, token)| (token.to_string(), id as u32)).collect();
+        let model = tokenizers::models::wordlevel::WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+
+        tokenizers::Tokenizer::new(model)
+    }
+
+    #[test]
+    fn should_resolve_pad_token() {
+        let with_pad = word_level_tokenizer(&["[UNK]", "hello", "[PAD]"]);
+        let sentencepiece_style = word_level_tokenizer(&["<s>", "<pad>", "</s>", "[UNK]"]);
+        let without_pad = word_level_tokenizer(&["[UNK]", "hello"]);
+
+        assert_eq!(resolve_pad_ids(&with_pad, None), Some((2, 0)));
+        assert_eq!(resolve_pad_ids(&sentencepiece_style, None), Some((1, 0)));
+        assert_eq!(resolve_pad_ids(&with_pad, Some(7)), Some((7, 0)));
+        assert_eq!(resolve_pad_ids(&without_pad, Some(0)), Some((0, 0)));
+        assert_eq!(resolve_pad_ids(&without_pad, None), None);
+    }
+
+    #[test]
+    fn should_keep_error_codes_stable() {
+        assert_eq!(SemanticError::TokenizeEncodeError("bad".into()).code(), 1);
+        assert_eq!(SemanticError::InitModelReadError(io::Error::from(io::ErrorKind::NotFound)).code(), 9);
+        assert_eq!(SemanticError::DimensionMismatch { expected: 384, actual: 128 }.code(), 11);
+        assert_eq!(SemanticError::InputTooLong { length: 600, max_length: 512 }.code(), 19);
+        assert_eq!(SemanticError::EmptyInput.code(), 25);
+        assert_eq!(SemanticError::Timeout(std::time::Duration::from_secs(1)).code(), 32);
+        assert_eq!(SemanticError::InvalidDimension { dim: 0, available: 128 }.code(), 33);
+    }
+
+    #[test]
+    fn should_only_retry_transient_errors() {
+        assert!(SemanticError::InitModelReadError(io::Error::from(io::ErrorKind::TimedOut)).is_retryable());
+        assert!(!SemanticError::InitModelReadError(io::Error::from(io::ErrorKind::NotFound)).is_retryable());
+        assert!(SemanticError::Timeout(std::time::Duration::from_secs(1)).is_retryable());
+        assert!(!SemanticError::DimensionMismatch { expected: 384, actual: 128 }.is_retryable());
+        assert!(!SemanticError::EmptyInput.is_retryable());
+        assert!(SemanticError::FetchFailed { url: "model.onnx".to_string(), status: Some(503) }.is_retryable());
+        assert!(!SemanticError::FetchFailed { url: "model.onnx".to_string(), status: Some(404) }.is_retryable());
+    }
+}
@@ -0,0 +1,62 @@
+/// Splits text into sentences after `.`, `!` or `?` (and any closing quotes or brackets) followed by whitespace.
+/// Text without terminal punctuation is a single sentence, and blank text has none.
+pub(crate) fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = vec![];
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+
+        while let Some((_, next)) = chars.peek() {
+            if matches!(next, '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’') {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        match chars.peek() {
+            Some((end, next)) if next.is_whitespace() => {
+                push_trimmed(&mut sentences, &text[start..*end]);
+                start = *end;
+            }
+            _ => {}
+        }
+    }
+
+    push_trimmed(&mut sentences, &text[start..]);
+    sentences
+}
+
+fn push_trimmed<'a>(sentences: &mut Vec<&'a str>, sentence: &'a str) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_on_terminal_punctuation() {
+        let sentences = split_sentences("Rust is fast. Is it safe? Yes!  It \"is.\" Done");
+
+        assert_eq!(sentences, vec!["Rust is fast.", "Is it safe?", "Yes!", "It \"is.\"", "Done"]);
+    }
+
+    #[test]
+    fn should_not_split_inside_numbers() {
+        assert_eq!(split_sentences("Pi is 3.14 roughly"), vec!["Pi is 3.14 roughly"]);
+    }
+
+    #[test]
+    fn should_handle_blank_text() {
+        assert!(split_sentences("").is_empty());
+        assert!(split_sentences("  \n ").is_empty());
+    }
+}
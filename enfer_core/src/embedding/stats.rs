@@ -0,0 +1,155 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A snapshot of the token counts of the sequences a [`crate::Semantic`] tokenized, collected when
+/// [`crate::SemanticConfig::collect_stats`] is enabled. Counts include special tokens and are taken after truncation,
+/// so `max` never exceeds the configured max length when truncation is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LengthStats {
+    /// The number of sequences recorded.
+    pub count: u64,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    /// The smallest token count at least 95% of the sequences fit in.
+    pub p95: usize,
+}
+
+/// The resident memory of the whole process, as reported by [`crate::Semantic::process_memory`]. This is not ORT's
+/// allocator statistics: it covers ORT's arenas along with everything else the process allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessMemory {
+    /// The resident set size right now.
+    pub resident_bytes: u64,
+    /// The largest resident set size the process reached so far.
+    pub peak_resident_bytes: u64,
+}
+
+impl ProcessMemory {
+    /// Reads the resident set sizes from `/proc/self/status`, `None` on other platforms or when it can't be read.
+    pub(crate) fn current() -> Option<ProcessMemory> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        // lines like `VmRSS:      123456 kB`
+        let kilobytes = |key: &str| status.lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|value| value.trim().strip_suffix("kB"))
+            .and_then(|value| value.trim().parse::<u64>().ok());
+
+        Some(ProcessMemory {
+            resident_bytes: kilobytes("VmRSS:")? * 1024,
+            peak_resident_bytes: kilobytes("VmHWM:")? * 1024,
+        })
+    }
+}
+
+/// Counts sequences per token count. Token counts are bounded by the max length, so the histogram stays small and
+/// percentiles are exact.
+#[derive(Debug, Default)]
+pub(crate) struct LengthHistogram {
+    counts: Mutex<Vec<u64>>,
+}
+
+impl LengthHistogram {
+    pub(crate) fn record(&self, length: usize) {
+        let mut counts = self.counts();
+        if counts.len() <= length {
+            counts.resize(length + 1, 0);
+        }
+        counts[length] += 1;
+    }
+
+    pub(crate) fn reset(&self) {
+        self.counts().clear();
+    }
+
+    pub(crate) fn snapshot(&self) -> LengthStats {
+        let counts = self.counts();
+        let count = counts.iter().sum::<u64>();
+        if count == 0 {
+            return LengthStats::default();
+        }
+
+        let recorded = || counts.iter().enumerate().filter(|(_, n)| **n > 0);
+        let total = recorded().map(|(length, n)| length as u64 * n).sum::<u64>();
+        let p95_rank = (count * 95).div_ceil(100);
+        let p95 = recorded()
+            .scan(0, |seen, (length, n)| {
+                *seen += n;
+                Some((length, *seen))
+            })
+            .find(|(_, seen)| *seen >= p95_rank)
+            .map_or(0, |(length, _)| length);
+
+        LengthStats {
+            count,
+            min: recorded().next().map_or(0, |(length, _)| length),
+            max: recorded().next_back().map_or(0, |(length, _)| length),
+            mean: total as f64 / count as f64,
+            p95,
+        }
+    }
+
+    /// Stats are best-effort, so a panic while they were locked doesn't stop later calls from recording.
+    fn counts(&self) -> MutexGuard<'_, Vec<u64>> {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_summarize_recorded_lengths() {
+        let histogram = LengthHistogram::default();
+        for length in 1..=100 {
+            histogram.record(length);
+        }
+
+        let stats = histogram.snapshot();
+
+        assert_eq!(stats, LengthStats { count: 100, min: 1, max: 100, mean: 50.5, p95: 95 });
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn should_read_process_memory() {
+        let memory = ProcessMemory::current().unwrap();
+
+        assert!(memory.resident_bytes > 0);
+        assert!(memory.peak_resident_bytes >= memory.resident_bytes);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "linux"))]
+    fn should_not_report_process_memory_elsewhere() {
+        assert_eq!(ProcessMemory::current(), None);
+    }
+
+    #[test]
+    fn should_keep_recording_after_poisoned_lock() {
+        let histogram = LengthHistogram::default();
+        let _ = std::panic::catch_unwind(|| {
+            let _guard = histogram.counts.lock().unwrap();
+            panic!("poison the lock");
+        });
+
+        histogram.record(3);
+
+        assert_eq!(histogram.snapshot().count, 1);
+    }
+
+    #[test]
+    fn should_start_over_after_reset() {
+        let histogram = LengthHistogram::default();
+        histogram.record(7);
+        assert_eq!(histogram.snapshot().max, 7);
+
+        histogram.reset();
+
+        assert_eq!(histogram.snapshot(), LengthStats::default());
+    }
+}
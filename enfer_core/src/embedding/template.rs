@@ -0,0 +1,50 @@
+use serde_json::Value;
+
+use crate::embedding::semantic::SemanticError;
+
+/// Replaces every `{field}` of the template with that field of the JSON object. Strings are inserted as they are,
+/// other values as JSON, and a field that is missing or `null` fails with [`SemanticError::MissingTemplateField`].
+/// A `{` without a closing `}` is kept as text.
+pub(crate) fn render(record: &Value, template: &str) -> Result<String, SemanticError> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else { break };
+        let field = &rest[start + 1..start + length];
+
+        rendered.push_str(&rest[..start]);
+        match record.get(field) {
+            Some(Value::String(text)) => rendered.push_str(text),
+            Some(Value::Null) | None => return Err(SemanticError::MissingTemplateField(field.to_string())),
+            Some(value) => rendered.push_str(&value.to_string()),
+        }
+        rest = &rest[start + length + 1..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn should_substitute_fields() {
+        let record = json!({ "title": "Rust", "body": "A systems language", "stars": 5 });
+
+        assert_eq!(render(&record, "{title}. {body}").unwrap(), "Rust. A systems language");
+        assert_eq!(render(&record, "{title} ({stars} stars) {").unwrap(), "Rust (5 stars) {");
+    }
+
+    #[test]
+    fn should_reject_missing_fields() {
+        let record = json!({ "title": "Rust", "body": null });
+
+        assert!(matches!(render(&record, "{title}. {summary}"), Err(SemanticError::MissingTemplateField(field)) if field == "summary"));
+        assert!(matches!(render(&record, "{body}"), Err(SemanticError::MissingTemplateField(_))));
+        assert!(matches!(render(&json!(["Rust"]), "{title}"), Err(SemanticError::MissingTemplateField(_))));
+    }
+}
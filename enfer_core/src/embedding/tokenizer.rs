@@ -0,0 +1,242 @@
+use std::borrow::Cow;
+
+use tokenizers::models::unigram::Unigram;
+use tokenizers::normalizers::precompiled::Precompiled;
+use tokenizers::normalizers::StripAccents;
+use tokenizers::pre_tokenizers::metaspace::{Metaspace, PrependScheme};
+use tokenizers::processors::template::TemplateProcessing;
+use tokenizers::{AddedToken, NormalizedString, Normalizer, Tokenizer};
+
+use crate::embedding::protobuf::{Fields, Value};
+use crate::embedding::semantic::SemanticError;
+
+/// The file format of the tokenizer bytes a [`crate::Semantic`] is built from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenizerFormat {
+    /// `Json` when the bytes start with `{`, `SentencePiece` otherwise.
+    #[default]
+    Auto,
+    /// A Hugging Face `tokenizer.json`.
+    Json,
+    /// A SentencePiece `tokenizer.model`, as shipped with many multilingual models. The ids are the positions of the
+    /// pieces in the model, so models that shift them, such as fairseq's XLM-R, still need their `tokenizer.json`.
+    SentencePiece,
+}
+
+impl TokenizerFormat {
+    fn detect(self, data: &[u8]) -> TokenizerFormat {
+        match self {
+            TokenizerFormat::Auto => match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+                Some(b'{') => TokenizerFormat::Json,
+                _ => TokenizerFormat::SentencePiece,
+            },
+            format => format,
+        }
+    }
+}
+
+/// A normalization applied to the text before the tokenizer sees it, for models trained on preprocessed text that
+/// the bundled tokenizer doesn't normalize the same way. They run in the order they are configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextNormalizer {
+    /// Lowercases the text with Unicode's rules.
+    Lowercase,
+    /// Unicode NFKC normalization, which folds compatibility characters such as `ﬁ` or full-width letters.
+    Nfkc,
+    /// Decomposes the characters and removes the combining marks, so `Café` becomes `Cafe`.
+    StripAccents,
+}
+
+/// Applies the normalizers in order, borrowing the text when there are none.
+pub(crate) fn normalize_text<'a>(text: &'a str, normalizers: &[TextNormalizer]) -> Result<Cow<'a, str>, SemanticError> {
+    if normalizers.is_empty() {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut normalized = NormalizedString::from(text);
+    for normalizer in normalizers {
+        match normalizer {
+            TextNormalizer::Lowercase => {
+                normalized.lowercase();
+            }
+            TextNormalizer::Nfkc => {
+                normalized.nfkc();
+            }
+            TextNormalizer::StripAccents => {
+                StripAccents.normalize(normalized.nfd()).map_err(SemanticError::TokenizeEncodeError)?;
+            }
+        }
+    }
+
+    Ok(Cow::Owned(normalized.get().to_string()))
+}
+
+pub(crate) fn load_tokenizer(data: Vec<u8>, format: TokenizerFormat) -> Result<Tokenizer, SemanticError> {
+    match format.detect(&data) {
+        TokenizerFormat::SentencePiece => from_sentencepiece(&data),
+        _ => Tokenizer::from_bytes(data).map_err(SemanticError::TokenizeEncodeByteError),
+    }
+}
+
+// piece types of `sentencepiece_model.proto`
+const PIECE_UNKNOWN: u64 = 2;
+const PIECE_CONTROL: u64 = 3;
+const PIECE_BYTE: u64 = 6;
+
+/// Builds a Unigram tokenizer the way Hugging Face converts SentencePiece models: the model's precompiled
+/// normalization, `▁` in place of spaces, and `<s> $A </s>` around the sequence when the model has both control pieces.
+fn from_sentencepiece(data: &[u8]) -> Result<Tokenizer, SemanticError> {
+    let model = parse_model(data).map_err(invalid)?;
+
+    let unk_id = model.pieces.iter().position(|piece| piece.kind == PIECE_UNKNOWN);
+    let byte_fallback = model.pieces.iter().any(|piece| piece.kind == PIECE_BYTE);
+    let vocab = model.pieces.iter().map(|piece| (piece.text.clone(), piece.score as f64)).collect();
+    let unigram = Unigram::from(vocab, unk_id, byte_fallback).map_err(SemanticError::TokenizeEncodeByteError)?;
+
+    let mut tokenizer = Tokenizer::new(unigram);
+    if !model.precompiled_charsmap.is_empty() {
+        let precompiled = Precompiled::from(&model.precompiled_charsmap)
+            .map_err(|e| SemanticError::TokenizeEncodeByteError(e.into()))?;
+        tokenizer.with_normalizer(precompiled);
+    }
+
+    let prepend_scheme = if model.add_dummy_prefix { PrependScheme::Always } else { PrependScheme::Never };
+    tokenizer.with_pre_tokenizer(Metaspace::new('▁', prepend_scheme, true));
+    tokenizer.with_decoder(Metaspace::new('▁', prepend_scheme, true));
+
+    let controls = model.pieces.iter()
+        .enumerate()
+        .filter(|(_, piece)| piece.kind == PIECE_CONTROL)
+        .map(|(id, piece)| (piece.text.as_str(), id as u32))
+        .collect::<Vec<_>>();
+    let find = |text: &str| controls.iter().find(|(control, _)| *control == text).copied();
+    if let (Some(bos), Some(eos)) = (find("<s>"), find("</s>")) {
+        let processor = TemplateProcessing::builder()
+            .try_single("<s> $A </s>").map_err(invalid)?
+            .special_tokens(vec![bos, eos])
+            .build()
+            .map_err(|e| invalid(e.to_string()))?;
+        tokenizer.with_post_processor(processor);
+    }
+    tokenizer.add_special_tokens(&controls.iter().map(|(text, _)| AddedToken::from(*text, true)).collect::<Vec<_>>());
+
+    Ok(tokenizer)
+}
+
+fn invalid(reason: String) -> SemanticError {
+    SemanticError::TokenizeEncodeByteError(format!("invalid SentencePiece model: {}", reason).into())
+}
+
+struct Piece {
+    text: String,
+    score: f32,
+    kind: u64,
+}
+
+struct SentencePieceModel {
+    pieces: Vec<Piece>,
+    precompiled_charsmap: Vec<u8>,
+    add_dummy_prefix: bool,
+}
+
+/// Reads the fields of a `ModelProto` the tokenizer needs, skipping everything else.
+fn parse_model(data: &[u8]) -> Result<SentencePieceModel, String> {
+    let mut model = SentencePieceModel { pieces: vec![], precompiled_charsmap: vec![], add_dummy_prefix: true };
+
+    let mut fields = Fields::new(data);
+    while let Some((field, value)) = fields.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(bytes)) => model.pieces.push(parse_piece(bytes)?),
+            (3, Value::Bytes(bytes)) => {
+                let mut normalizer = Fields::new(bytes);
+                while let Some((field, value)) = normalizer.next_field()? {
+                    match (field, value) {
+                        (2, Value::Bytes(charsmap)) => model.precompiled_charsmap = charsmap.to_vec(),
+                        (3, Value::Varint(enabled)) => model.add_dummy_prefix = enabled != 0,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if model.pieces.is_empty() {
+        return Err("no pieces".to_string());
+    }
+
+    Ok(model)
+}
+
+fn parse_piece(data: &[u8]) -> Result<Piece, String> {
+    let mut piece = Piece { text: String::new(), score: 0.0, kind: 1 };
+
+    let mut fields = Fields::new(data);
+    while let Some((field, value)) = fields.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(text)) => piece.text = String::from_utf8(text.to_vec()).map_err(|e| e.to_string())?,
+            (2, Value::Fixed32(bits)) => piece.score = f32::from_bits(bits),
+            (3, Value::Varint(kind)) => piece.kind = kind,
+            _ => {}
+        }
+    }
+
+    Ok(piece)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes_field(field: u8, bytes: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![field << 3 | 2, bytes.len() as u8];
+        encoded.extend_from_slice(bytes);
+        encoded
+    }
+
+    fn piece(text: &str, score: f32, kind: u8) -> Vec<u8> {
+        let mut encoded = bytes_field(1, text.as_bytes());
+        encoded.push(2 << 3 | 5);
+        encoded.extend_from_slice(&score.to_le_bytes());
+        encoded.extend_from_slice(&[3 << 3, kind]);
+        bytes_field(1, &encoded)
+    }
+
+    fn model() -> Vec<u8> {
+        [piece("<unk>", 0.0, 2), piece("<s>", 0.0, 3), piece("</s>", 0.0, 3), piece("▁", -2.0, 1),
+            piece("▁grüße", -1.0, 1), piece("▁日本", -1.0, 1), piece("語", -1.5, 1)].concat()
+    }
+
+    #[test]
+    fn should_normalize_text_in_order() {
+        assert_eq!(normalize_text("Café", &[]).unwrap(), "Café");
+        assert_eq!(normalize_text("Café", &[TextNormalizer::StripAccents]).unwrap(), "Cafe");
+        assert_eq!(normalize_text("ＣＡＦÉ ﬁne", &[TextNormalizer::Nfkc, TextNormalizer::Lowercase]).unwrap(), "café fine");
+        assert_eq!(normalize_text("Crème Brûlée", &[TextNormalizer::Lowercase, TextNormalizer::StripAccents]).unwrap(), "creme brulee");
+    }
+
+    #[test]
+    fn should_detect_format() {
+        assert_eq!(TokenizerFormat::Auto.detect(b"  {\"version\": \"1.0\"}"), TokenizerFormat::Json);
+        assert_eq!(TokenizerFormat::Auto.detect(&model()), TokenizerFormat::SentencePiece);
+        assert_eq!(TokenizerFormat::Json.detect(&model()), TokenizerFormat::Json);
+    }
+
+    #[test]
+    fn should_tokenize_non_ascii_text_with_sentencepiece() {
+        let tokenizer = load_tokenizer(model(), TokenizerFormat::Auto).unwrap();
+
+        let encoding = tokenizer.encode("grüße 日本語", true).unwrap();
+
+        assert_eq!(encoding.get_tokens(), ["<s>", "▁grüße", "▁日本", "語", "</s>"]);
+        assert_eq!(encoding.get_ids(), [1, 4, 5, 6, 2]);
+    }
+
+    #[test]
+    fn should_reject_truncated_model() {
+        let model = model();
+
+        assert!(matches!(load_tokenizer(model[..model.len() - 3].to_vec(), TokenizerFormat::SentencePiece),
+            Err(SemanticError::TokenizeEncodeByteError(_))));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::embedding::protobuf::{Fields, Value};
+use crate::embedding::semantic::SemanticError;
+
+/// Name suffixes of the token embedding table in BERT/RoBERTa-style and decoder-style exports.
+const EMBEDDING_TABLE_SUFFIXES: [&str; 2] = ["word_embeddings.weight", "embed_tokens.weight"];
+
+/// Fails when the tokenizer produces ids past the end of the model's token embedding table, which ORT would
+/// otherwise only report as an opaque out of range `Gather` at the first inference. Models whose table can't be
+/// found, e.g. because the exporter renamed it, aren't checked.
+pub(crate) fn validate_vocab(model: &[u8], vocab_size: usize) -> Result<(), SemanticError> {
+    match embedding_table_rows(model) {
+        Some(embedding_rows) if vocab_size > embedding_rows => {
+            Err(SemanticError::ModelTokenizerMismatch { vocab_size, embedding_rows })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reads the number of rows of the token embedding initializer from the ONNX `ModelProto`, without loading the weights.
+fn embedding_table_rows(model: &[u8]) -> Option<usize> {
+    // ModelProto.graph = 7, GraphProto.initializer = 5
+    let graph = message_fields(model, 7).ok()?.into_iter().next()?;
+    message_fields(graph, 5).ok()?.into_iter().find_map(|tensor| {
+        let (name, dims) = read_tensor(tensor).ok()?;
+        if !EMBEDDING_TABLE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+            return None;
+        }
+
+        dims.first().map(|rows| *rows as usize)
+    })
+}
+
+fn message_fields(message: &[u8], number: u64) -> Result<Vec<&[u8]>, String> {
+    let mut found = vec![];
+    let mut fields = Fields::new(message);
+    while let Some((field, value)) = fields.next_field()? {
+        if let (true, Value::Bytes(bytes)) = (field == number, value) {
+            found.push(bytes);
+        }
+    }
+
+    Ok(found)
+}
+
+/// The name (8) and dims (1, packed or not) of a `TensorProto`.
+fn read_tensor(tensor: &[u8]) -> Result<(String, Vec<u64>), String> {
+    let (mut name, mut dims) = (String::new(), vec![]);
+
+    let mut fields = Fields::new(tensor);
+    while let Some((field, value)) = fields.next_field()? {
+        match (field, value) {
+            (1, Value::Varint(dim)) => dims.push(dim),
+            (1, Value::Bytes(packed)) => {
+                let mut packed = Fields::new(packed);
+                while !packed.is_empty() {
+                    dims.push(packed.varint()?);
+                }
+            }
+            (8, Value::Bytes(bytes)) => name = String::from_utf8_lossy(bytes).into_owned(),
+            _ => {}
+        }
+    }
+
+    Ok((name, dims))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut encoded = vec![];
+        while value >= 0x80 {
+            encoded.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        encoded.push(value as u8);
+        encoded
+    }
+
+    fn bytes_field(field: u64, bytes: &[u8]) -> Vec<u8> {
+        [varint(field << 3 | 2), varint(bytes.len() as u64), bytes.to_vec()].concat()
+    }
+
+    fn model(name: &str, rows: u64) -> Vec<u8> {
+        let dims = [varint(rows), varint(128)].concat();
+        let tensor = [bytes_field(1, &dims), bytes_field(8, name.as_bytes()), bytes_field(9, &[0; 16])].concat();
+        let graph = [bytes_field(1, b"a node"), bytes_field(5, &tensor)].concat();
+
+        [varint(1 << 3), varint(8), bytes_field(7, &graph)].concat()
+    }
+
+    #[test]
+    fn should_read_embedding_table_rows() {
+        assert_eq!(embedding_table_rows(&model("embeddings.word_embeddings.weight", 30522)), Some(30522));
+        assert_eq!(embedding_table_rows(&model("onnx::Gather_0", 30522)), None);
+        assert_eq!(embedding_table_rows(b"not a model"), None);
+    }
+
+    #[test]
+    fn should_reject_vocab_larger_than_embedding_table() {
+        let model = model("model.embed_tokens.weight", 30522);
+
+        assert!(validate_vocab(&model, 30522).is_ok());
+        assert!(matches!(validate_vocab(&model, 250002),
+            Err(SemanticError::ModelTokenizerMismatch { vocab_size: 250002, embedding_rows: 30522 })));
+    }
+}
@@ -1,22 +1,63 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+extern crate alloc;
+
 use std::sync::Arc;
 
 pub use document::Document;
 pub use document::Metadata;
+pub use embedding::CachedSemantic;
+pub use embedding::EmbedOptions;
+pub use embedding::EmbedOutput;
+pub use embedding::Embedder;
 pub use embedding::Embedding;
+pub use embedding::ExecutionProvider;
+pub use embedding::ExecutionProviderKind;
+pub use embedding::HashEmbedder;
+pub use embedding::InputNames;
+pub use embedding::LengthStats;
+pub use embedding::LogLevel;
+pub use embedding::ProcessMemory;
+pub use embedding::ModelInfo;
+pub use embedding::ModelKind;
+pub use embedding::OptimizationLevel;
+pub use embedding::OutputSelector;
+pub use embedding::PaddingSide;
+pub use embedding::PoolingScope;
+pub use embedding::PoolingStrategy;
+pub use embedding::Precision;
 pub use embedding::Semantic;
+pub use embedding::SemanticBuilder;
+pub use embedding::SemanticConfig;
+pub use embedding::SemanticPool;
+#[cfg(feature = "npy")]
+pub use embedding::read_npy;
+#[cfg(feature = "npy")]
+pub use embedding::write_npy;
+pub use embedding::TensorSpec;
+pub use embedding::TextNormalizer;
+pub use embedding::TokenizerFormat;
 pub use embedding::semantic::SemanticError;
 pub use similarity::CosineSimilarity;
 pub use similarity::DocumentMatch;
+pub use similarity::hamming_distance;
+pub use similarity::kmeans;
+pub use similarity::DistanceMetric;
 pub use similarity::EmbeddingMatch;
 pub use similarity::RelevanceScore;
 pub use similarity::Similarity;
+pub use similarity::similarity_matrix;
 pub use store::EmbeddingStore;
 pub use store::InMemoryEmbeddingStore;
+pub use store::VectorIndex;
+pub use store::ProductQuantizer;
 
 pub mod document;
 pub mod embedding;
 pub mod similarity;
 pub mod store;
+#[cfg(feature = "server")]
+pub mod server;
 
 pub fn get_cosine_similarity() -> Arc<dyn Similarity> {
     Arc::new(CosineSimilarity {})
@@ -28,10 +69,7 @@ pub fn init_semantic(model: Vec<u8>, tokenizer_data: Vec<u8>) -> Result<Arc<Sema
 }
 
 pub fn init_semantic_with_path(model_path: &str, tokenizer_path: &str) -> Result<Arc<Semantic>, SemanticError> {
-    let model = std::fs::read(model_path).map_err(|_| SemanticError::InitModelReadError)?;
-    let tokenizer_data = std::fs::read(tokenizer_path).map_err(|_| SemanticError::InitTokenizerReadError)?;
-
-    let result = Semantic::init_semantic(model, tokenizer_data)?;
+    let result = Semantic::from_files(model_path, tokenizer_path)?;
     Ok(Arc::new(result))
 }
 
@@ -48,6 +86,7 @@ mod tests {
         let semantic = init_semantic(model, tokenizer_data).unwrap();
         let embedding = semantic.embed("hello world").unwrap();
         assert_eq!(embedding.len(), 128);
+        assert_eq!(semantic.embedding_dim(), 128);
     }
 
     #[test]
@@ -69,6 +108,767 @@ mod tests {
         let vec = embedding_store.find_relevant(pure_text_hello, 1, 0.0);
         assert_eq!(vec.len(), 1);
     }
+
+    #[test]
+    fn should_report_missing_files() {
+        let missing_model = Semantic::from_files("../model/missing.onnx", "../model/tokenizer.json");
+        assert!(matches!(missing_model, Err(SemanticError::InitModelReadError(_))));
+
+        let missing_tokenizer = Semantic::from_files("Cargo.toml", "../model/missing.json");
+        assert!(matches!(missing_tokenizer, Err(SemanticError::InitTokenizerReadError(_))));
+
+        let missing_model = Semantic::init_semantic_with_paths("../model/missing.onnx", "../model/tokenizer.json");
+        let error = missing_model.err().unwrap();
+        let source = std::error::Error::source(&error).and_then(|e| e.downcast_ref::<std::io::Error>());
+        assert_eq!(source.map(|e| e.kind()), Some(std::io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_release_model_on_drop() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+
+        for _ in 0..16 {
+            let semantic = Semantic::init_semantic(model.clone(), tokenizer_data.clone()).unwrap();
+            drop(semantic);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_find_near_identical_sentences_similar() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let a = semantic.embed("The cat sits on the mat.").unwrap();
+        let b = semantic.embed("The cat sits on the mat").unwrap();
+
+        assert!(a.cosine_similarity(&b).unwrap() > 0.9);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_report_active_cpu_provider() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+
+        let semantic = Semantic::builder(model, tokenizer_data)
+            .execution_provider(ExecutionProvider::Cpu)
+            .build()
+            .unwrap();
+
+        assert_eq!(semantic.active_provider(), ExecutionProviderKind::Cpu);
+        assert_eq!(semantic.execution_provider(), &ExecutionProvider::Cpu);
+    }
+
+    #[test]
+    #[cfg(feature = "cuda")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_build_session_with_cuda_requested() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+
+        let semantic = Semantic::builder(model, tokenizer_data)
+            .execution_provider(ExecutionProvider::Cuda { device_id: 0 })
+            .build()
+            .unwrap();
+        assert_eq!(semantic.embed("hello world").unwrap().len(), 128);
+    }
+
+    #[test]
+    #[cfg(feature = "directml")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_build_session_with_directml_requested() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+
+        let semantic = Semantic::builder(model, tokenizer_data)
+            .execution_provider(ExecutionProvider::DirectML { device_id: 0 })
+            .build()
+            .unwrap();
+        assert_eq!(semantic.embed("hello world").unwrap().len(), 128);
+    }
+
+    #[test]
+    #[cfg(feature = "openvino")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_build_session_with_openvino_requested() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+
+        let semantic = Semantic::builder(model, tokenizer_data)
+            .execution_provider(ExecutionProvider::OpenVINO { device_type: "AUTO".to_string() })
+            .build()
+            .unwrap();
+        assert_eq!(semantic.embed("hello world").unwrap().len(), 128);
+    }
+
+    #[test]
+    #[cfg(feature = "tensorrt")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_build_session_with_tensorrt_requested() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let cache_dir = std::env::temp_dir().join("inference_core_trt_cache");
+
+        let semantic = Semantic::builder(model, tokenizer_data)
+            .execution_provider(ExecutionProvider::TensorRT { device_id: 0, engine_cache_dir: Some(cache_dir) })
+            .build()
+            .unwrap();
+        assert!(matches!(semantic.execution_provider(),
+            ExecutionProvider::TensorRT { .. } | ExecutionProvider::Cuda { .. } | ExecutionProvider::Cpu));
+        assert_eq!(semantic.embed("hello world").unwrap().len(), 128);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_truncate_long_inputs() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let long_text = "word ".repeat(2000);
+
+        let semantic = Semantic::builder(model.clone(), tokenizer_data.clone()).build().unwrap();
+        assert_eq!(semantic.embed(&long_text).unwrap().len(), 128);
+
+        let output = semantic.embed_detailed(&long_text).unwrap();
+        assert_eq!(output.token_count, 512);
+        assert!(output.truncated);
+
+        let strict = Semantic::builder(model, tokenizer_data).truncate(false).build().unwrap();
+        assert!(matches!(strict.embed(&long_text), Err(SemanticError::InputTooLong { .. })));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_long_documents_in_windows() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let document = "Edge inference runs small models on devices. ".repeat(300);
+
+        assert_eq!(semantic.embed_long(&document, 128, 16).unwrap().len(), 128);
+        assert!(matches!(semantic.embed_long(&document, 16, 16), Err(SemanticError::InvalidWindow { .. })));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_fit_windows_with_special_tokens_in_max_length() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let strict = Semantic::builder(model, tokenizer_data).truncate(false).build().unwrap();
+        let document = "Edge inference runs small models on devices. ".repeat(300);
+
+        assert!(matches!(strict.embed_long(&document, 512, 32), Err(SemanticError::InvalidWindow { window: 512, overlap: 32 })));
+        assert_eq!(strict.embed_long(&document, 510, 32).unwrap().len(), strict.embedding_dim());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_batch_with_padding() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        assert!(semantic.embed_batch(&[]).unwrap().is_empty());
+
+        let short = "hello";
+        let long = "print('hello world') is the first program most people ever write";
+        let batch = semantic.embed_batch(&[short, long]).unwrap();
+        assert_eq!(batch.len(), 2);
+
+        let single = semantic.embed(short).unwrap();
+        for (a, b) in batch[0].iter().zip(single.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_warm_up_session() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        assert!(semantic.warmup().is_ok());
+
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        assert!(SemanticBuilder::new(model, tokenizer_data).warmup(true).build().is_ok());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_into_reused_buffer() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let mut out = vec![1.0; 1024];
+
+        semantic.embed_to("Hello world!", &mut out).unwrap();
+
+        assert_eq!(out.len(), semantic.embedding_dim());
+        assert_eq!(out, semantic.embed("Hello world!").unwrap().0);
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray-output")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_into_array() {
+        let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap().with_normalization(true);
+
+        let array = semantic.embed_array("Hello world!").unwrap();
+
+        assert_eq!(array.len(), semantic.embedding_dim());
+        for (a, b) in array.iter().zip(semantic.embed("Hello world!").unwrap().iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray-output")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_stack_batch_into_matrix() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let texts = ["Hello world!", "A much longer sentence to embed"];
+
+        let matrix = semantic.embed_matrix(&texts).unwrap();
+
+        assert_eq!(matrix.dim(), (2, semantic.embedding_dim()));
+        for (row, embedding) in matrix.rows().into_iter().zip(semantic.embed_batch(&texts).unwrap()) {
+            assert_eq!(row.to_vec(), embedding.0);
+        }
+        assert!(matches!(semantic.embed_matrix(&[]), Err(SemanticError::EmptyInput)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "wasm"))]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_give_up_after_timeout() {
+        let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let embedding = semantic.embed_with_timeout("Hello world!", std::time::Duration::from_secs(30)).unwrap();
+        assert_eq!(embedding.0, semantic.embed("Hello world!").unwrap().0);
+
+        let timed_out = semantic.embed_with_timeout("Hello world!", std::time::Duration::ZERO);
+        assert!(matches!(timed_out, Err(SemanticError::Timeout(_))));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_concurrently_from_shared_instance() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let expected = semantic.embed("Hello world!").unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let semantic = semantic.clone();
+                std::thread::spawn(move || semantic.embed("Hello world!").unwrap())
+            })
+            .collect();
+
+        for handle in handles {
+            let embedding = handle.join().unwrap();
+            for (a, b) in embedding.iter().zip(expected.iter()) {
+                assert!((a - b).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn should_reject_empty_pool() {
+        let pool = SemanticBuilder::new(vec![], vec![]).pool_size(0).build_pool();
+
+        assert!(matches!(pool, Err(SemanticError::InvalidPoolSize(0))));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_in_parallel_with_pool() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let pool = Arc::new(SemanticBuilder::new(model, tokenizer_data).pool_size(2).build_pool().unwrap());
+        assert_eq!(pool.size(), 2);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let pool = pool.clone();
+                std::thread::spawn(move || pool.embed("Hello world!").unwrap())
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().len(), pool.embedding_dim());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_every_token() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let tokens = semantic.embed_tokens("Hello world!").unwrap();
+
+        let names = tokens.iter().map(|(token, _)| token.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["[CLS]", "hello", "world", "!", "[SEP]"]);
+        assert!(tokens.iter().all(|(_, embedding)| embedding.len() == semantic.embedding_dim()));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_skip_special_token_embeddings() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let all = semantic.embed_tokens_with("Hello world!", false).unwrap();
+        let content = semantic.embed_tokens_with("Hello world!", true).unwrap();
+
+        assert_eq!(all.len(), 5);
+        assert_eq!(content.len(), 3);
+        assert_eq!(content.iter().map(|(token, _)| token.as_str()).collect::<Vec<_>>(), vec!["hello", "world", "!"]);
+        assert_eq!(content[0].1, all[1].1);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_each_sentence() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let sentences = semantic.embed_sentences("Rust is fast. It is also safe").unwrap();
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(sentences[0].0, "Rust is fast.");
+        assert_eq!(sentences[1].1.len(), semantic.embedding_dim());
+
+        assert!(semantic.embed_sentences("").unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_prepend_instruction_prefixes() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let semantic = SemanticBuilder::new(model, tokenizer_data).query_prefix("query: ").build().unwrap();
+
+        assert_eq!(semantic.embed_query("rust").unwrap().0, semantic.embed("query: rust").unwrap().0);
+        assert_eq!(semantic.embed_passage("rust").unwrap().0, semantic.embed("rust").unwrap().0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_without_special_tokens() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let with_special = Semantic::init_semantic(model.clone(), tokenizer_data.clone()).unwrap();
+        let without_special = SemanticBuilder::new(model, tokenizer_data).add_special_tokens(false).build().unwrap();
+
+        let names = without_special.embed_tokens("Hello world!").unwrap().into_iter().map(|(token, _)| token).collect::<Vec<_>>();
+        assert_eq!(names, vec!["hello", "world", "!"]);
+        assert_ne!(with_special.embed("Hello world!").unwrap().0, without_special.embed("Hello world!").unwrap().0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_bit_identically_when_deterministic() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let semantic = SemanticBuilder::new(model, tokenizer_data).deterministic(true).build().unwrap();
+
+        let first = semantic.embed("Hello world!").unwrap();
+        let second = semantic.embed("Hello world!").unwrap();
+
+        assert!(first.iter().zip(second.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_override_options_per_call() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let text = "the same input, embedded with different options";
+        let raw = semantic.embed(text).unwrap();
+
+        let normalized = semantic.embed_with_options(text, &EmbedOptions { normalize: Some(true), ..Default::default() }).unwrap();
+        assert!((normalized.norm() - 1.0).abs() < 1e-5);
+        assert!(normalized.approx_eq(&raw.normalized(), 1e-6));
+
+        let unchanged = semantic.embed_with_options(text, &EmbedOptions { normalize: Some(false), ..Default::default() }).unwrap();
+        assert!(unchanged.approx_eq(&raw, 1e-6));
+
+        let cls = semantic.embed_with_options(text, &EmbedOptions { pooling: Some(PoolingStrategy::Cls), ..Default::default() }).unwrap();
+        assert!(cls.approx_eq(&semantic.embed_with(text, PoolingStrategy::Cls).unwrap(), 1e-6));
+
+        let prefixed = semantic.embed_with_options(text, &EmbedOptions { prefix: Some("query: ".to_string()), ..Default::default() }).unwrap();
+        assert!(prefixed.approx_eq(&semantic.embed(&format!("query: {}", text)).unwrap(), 1e-6));
+
+        let short = semantic.embed_with_options(text, &EmbedOptions { max_length: Some(4), ..Default::default() }).unwrap();
+        let detailed = semantic.embed_detailed("the same").unwrap();
+        assert_eq!(detailed.token_count, 4);
+        assert!(short.approx_eq(&detailed.embedding, 1e-5));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_templated_json_record() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let record = serde_json::json!({ "title": "Rust", "body": "A language empowering everyone", "id": 7 });
+
+        let embedding = semantic.embed_json(&record, "{title}. {body}").unwrap();
+
+        assert!(embedding.approx_eq(&semantic.embed("Rust. A language empowering everyone").unwrap(), 1e-6));
+        assert!(matches!(semantic.embed_json(&record, "{title}. {summary}"), Err(SemanticError::MissingTemplateField(_))));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_reject_text_without_tokens() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let semantic = Semantic::builder(model, tokenizer_data).add_special_tokens(false).build().unwrap();
+
+        // BERT's normalizer strips control characters, which aren't whitespace to the blank check
+        let control = "\u{0}\u{7}";
+        assert!(matches!(semantic.embed(control), Err(SemanticError::EmptyTokenization)));
+        assert!(matches!(semantic.embed_batch(&["hello", control]), Err(SemanticError::EmptyTokenization)));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_report_progress_once_per_sub_batch() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let texts = ["hello"; 6];
+
+        let whole = Semantic::init_semantic(model.clone(), tokenizer_data.clone()).unwrap();
+        let mut calls = vec![];
+        whole.embed_batch_with_progress(&texts, |processed, total| calls.push((processed, total))).unwrap();
+        assert_eq!(calls, vec![(6, 6)]);
+
+        // "hello" is 3 tokens with [CLS] and [SEP], so a budget of 6 tokens fits 2 sequences per sub-batch
+        let split = Semantic::builder(model, tokenizer_data).max_batch_tokens(6).build().unwrap();
+        let mut calls = vec![];
+        let embeddings = split.embed_batch_with_progress(&texts, |processed, total| calls.push((processed, total))).unwrap();
+        assert_eq!(embeddings.len(), 6);
+        assert_eq!(calls, vec![(2, 6), (4, 6), (6, 6)]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_pre_tokenized_ids() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let tokenizer = tokenizers::Tokenizer::from_bytes(&tokenizer_data).unwrap();
+        let semantic = Semantic::init_semantic(model, tokenizer_data).unwrap();
+
+        let encoding = tokenizer.encode("hello world", true).unwrap();
+        let ids = encoding.get_ids().iter().map(|id| *id as i64).collect::<Vec<_>>();
+
+        let expected = semantic.embed("hello world").unwrap();
+        assert!(semantic.embed_ids(&ids, None).unwrap().approx_eq(&expected, 1e-5));
+        assert!(semantic.embed_ids(&ids, Some(&vec![1; ids.len()])).unwrap().approx_eq(&expected, 1e-5));
+        assert!(matches!(semantic.embed_ids(&ids, Some(&[1, 1])), Err(SemanticError::DimensionMismatch { .. })));
+        assert!(matches!(semantic.embed_ids(&[], None), Err(SemanticError::EmptyInput)));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_batches_of_pre_tokenized_ids() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let tokenizer = tokenizers::Tokenizer::from_bytes(&tokenizer_data).unwrap();
+        let semantic = Semantic::init_semantic(model, tokenizer_data).unwrap();
+
+        let texts = ["a sentence long enough to pad the other one", "hello world"];
+        let batch = texts.iter()
+            .map(|text| tokenizer.encode(*text, true).unwrap().get_ids().iter().map(|id| *id as i64).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_ne!(batch[0].len(), batch[1].len());
+
+        let embeddings = semantic.embed_ids_batch(&batch, None).unwrap();
+        let expected = semantic.embed_batch(&texts).unwrap();
+        assert_eq!(embeddings.len(), 2);
+        assert!(embeddings[0].approx_eq(&expected[0], 1e-4));
+        assert!(embeddings[1].approx_eq(&expected[1], 1e-4));
+        assert!(embeddings[1].approx_eq(&semantic.embed_ids(&batch[1], None).unwrap(), 1e-4));
+
+        let masks = batch.iter().map(|ids| vec![1; ids.len()]).collect::<Vec<_>>();
+        assert!(semantic.embed_ids_batch(&batch, Some(&masks)).unwrap()[1].approx_eq(&expected[1], 1e-4));
+        assert!(matches!(semantic.embed_ids_batch(&batch, Some(&masks[..1])), Err(SemanticError::DimensionMismatch { .. })));
+        assert!(matches!(semantic.embed_ids_batch(&batch, Some(&[vec![1], vec![1]])), Err(SemanticError::DimensionMismatch { .. })));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_truncate_embeddings_to_output_dim() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let full = Semantic::init_semantic(model.clone(), tokenizer_data.clone()).unwrap();
+        let truncated = Semantic::builder(model.clone(), tokenizer_data.clone())
+            .output_dim(64)
+            .normalize(true)
+            .build()
+            .unwrap();
+
+        let embedding = truncated.embed("hello world").unwrap();
+        assert_eq!(truncated.embedding_dim(), 64);
+        assert_eq!(embedding.len(), 64);
+        assert!((embedding.norm() - 1.0).abs() < 1e-5);
+        assert!(embedding.approx_eq(&full.embed("hello world").unwrap().truncate_dim(64).unwrap().normalized(), 1e-5));
+        assert_eq!(truncated.embed_batch(&["hello world", "hi"]).unwrap()[1].len(), 64);
+
+        assert!(matches!(
+            Semantic::builder(model, tokenizer_data).output_dim(129).build(),
+            Err(SemanticError::InvalidDimension { dim: 129, available: 128 })
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_normalize_text_before_tokenizing() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let stripping = Semantic::builder(model.clone(), tokenizer_data.clone())
+            .text_normalizers(vec![TextNormalizer::StripAccents])
+            .build()
+            .unwrap();
+        let plain = Semantic::init_semantic(model, tokenizer_data).unwrap();
+
+        assert!(stripping.embed("Café").unwrap().approx_eq(&plain.embed("Cafe").unwrap(), 1e-6));
+        assert!(stripping.embed_batch(&["Café"]).unwrap()[0].approx_eq(&plain.embed("Cafe").unwrap(), 1e-4));
+        // tokenizers that strip accents themselves, such as uncased BERT ones, already embed both the same
+        let stripped_by_tokenizer = plain.embed_tokens("Café").unwrap().iter().any(|(token, _)| token == "cafe");
+        assert_eq!(stripped_by_tokenizer, stripping.embed("Café").unwrap().approx_eq(&plain.embed("Café").unwrap(), 1e-6));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_report_process_memory_after_warmup() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        semantic.embed("warm up the session").unwrap();
+
+        let memory = semantic.process_memory().unwrap();
+
+        // the model alone is bigger than a megabyte once loaded
+        assert!(memory.resident_bytes > 1 << 20);
+        assert!(memory.peak_resident_bytes >= memory.resident_bytes);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_build_session_without_memory_pattern() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let semantic = Semantic::builder(model, tokenizer_data).enable_memory_pattern(false).build().unwrap();
+
+        let short = semantic.embed("hello").unwrap();
+        let batched = semantic.embed_batch(&["hello", "a longer sentence with a different shape"]).unwrap();
+
+        assert!(short.approx_eq(&batched[0], 1e-4));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_build_session_with_custom_thread_counts() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let sequential = Semantic::init_semantic(model.clone(), tokenizer_data.clone()).unwrap();
+
+        let parallel = Semantic::builder(model.clone(), tokenizer_data.clone())
+            .intra_threads(2)
+            .inter_threads(2)
+            .build()
+            .unwrap();
+
+        let expected = sequential.embed("hello world").unwrap();
+        assert!(parallel.embed("hello world").unwrap().approx_eq(&expected, 1e-5));
+        assert!(matches!(
+            Semantic::builder(model, tokenizer_data).inter_threads(0).build(),
+            Err(SemanticError::InvalidThreadCount(0))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_same_input_approximately_equally() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let hello = semantic.embed("Hello world!").unwrap();
+        let batched = semantic.embed_batch(&["Hello world!", "a much longer sentence that forces padding"]).unwrap();
+
+        assert!(hello.approx_eq(&batched[0], 1e-4));
+        assert!(!hello.approx_eq(&batched[1], 1e-4));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_record_token_lengths() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let semantic = Semantic::builder(model, tokenizer_data).collect_stats(true).build().unwrap();
+        assert_eq!(semantic.stats().count, 0);
+
+        let texts = ["hello", "hello world", "hello hello hello hello world"];
+        semantic.embed_batch(&texts[..2]).unwrap();
+        semantic.clone().embed(texts[2]).unwrap();
+
+        let stats = semantic.stats();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.min, 3);
+        assert_eq!(stats.max, 7);
+
+        semantic.reset_stats();
+        assert_eq!(semantic.stats().count, 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_pool_many_ways_in_one_pass() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let text = "Hello world!";
+
+        let embeddings = semantic.embed_multi(text, &[PoolingStrategy::Cls, PoolingStrategy::Mean, PoolingStrategy::Max]).unwrap();
+
+        assert_eq!(embeddings.len(), 3);
+        assert_eq!(embeddings[0].0, semantic.embed_with(text, PoolingStrategy::Cls).unwrap().0);
+        assert_eq!(embeddings[1].0, semantic.embed_with(text, PoolingStrategy::Mean).unwrap().0);
+        assert_eq!(embeddings[2].0, semantic.embed_with(text, PoolingStrategy::Max).unwrap().0);
+        assert!(semantic.embed_multi(text, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_pool_over_token_range() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let full = Semantic::init_semantic(model.clone(), tokenizer_data.clone()).unwrap();
+        let prefix = SemanticBuilder::new(model, tokenizer_data).pooling_scope(PoolingScope::Range { start: 0, end: 3 }).build().unwrap();
+
+        let text = "the query part, followed by a much longer document part";
+        assert_ne!(prefix.embed(text).unwrap().0, full.embed(text).unwrap().0);
+        assert_eq!(prefix.embed_batch(&[text, "short"]).unwrap()[0].0.len(), prefix.embedding_dim());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_skip_inference_for_cached_inputs() {
+        let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap().with_cache(8);
+
+        let first = semantic.embed("Hello world!").unwrap();
+        let second = semantic.embed("Hello world!").unwrap();
+        assert_eq!(first.0, second.0);
+        assert_eq!(semantic.inference_count(), 1);
+
+        semantic.clear();
+        semantic.embed("Hello world!").unwrap();
+        assert_eq!(semantic.inference_count(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_reject_blank_input() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        assert!(matches!(semantic.embed(""), Err(SemanticError::EmptyInput)));
+        assert!(matches!(semantic.embed("   "), Err(SemanticError::EmptyInput)));
+        assert!(matches!(semantic.embed_batch(&["hello", " "]), Err(SemanticError::EmptyInput)));
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_embed_on_blocking_pool() {
+        let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let embedding = runtime.block_on(semantic.embed_async("Hello world!".to_string())).unwrap();
+
+        assert_eq!(embedding.0, semantic.embed("Hello world!").unwrap().0);
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_stream_embeddings_in_order() {
+        use futures::StreamExt;
+
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let pool = SemanticBuilder::new(model, tokenizer_data).pool_size(2).build_pool().unwrap();
+        let texts = vec!["first".to_string(), "a much longer second sentence".to_string(), "third".to_string()];
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let streamed: Vec<_> = runtime.block_on(pool.embed_stream(futures::stream::iter(texts.clone()), 2, true).collect());
+
+        assert_eq!(streamed.len(), 3);
+        for (text, embedding) in texts.iter().zip(streamed) {
+            assert_eq!(embedding.unwrap().0, pool.embed(text).unwrap().0);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_describe_model() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let info = semantic.model_info();
+
+        assert!(info.inputs.iter().any(|input| input.name == "input_ids" && input.dtype == "Int64"));
+        assert_eq!(info.outputs[0].shape.len(), 3);
+
+        let input_ids = info.inputs.iter().find(|input| input.name == "input_ids").unwrap();
+        assert_eq!(semantic.max_sequence_length(), Some(input_ids.shape[1]).filter(|length| *length > 0).map(|length| length as usize));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_report_vocab_size() {
+        let semantic = init_semantic_with_path("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        let tokens = semantic.embed_tokens("Hello world!").unwrap();
+
+        assert!(semantic.vocab_size() > tokens.len());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_keep_input_order_with_sub_batches() {
+        let model = std::fs::read("../model/model.onnx").unwrap();
+        let tokenizer_data = std::fs::read("../model/tokenizer.json").unwrap();
+        let semantic = SemanticBuilder::new(model, tokenizer_data).max_batch_tokens(32).build().unwrap();
+        let long = "a sentence that is quite a bit longer than the others around it ".repeat(3);
+        let sequences = ["short", long.as_str(), "tiny", "medium length text here"];
+
+        let batch = semantic.embed_batch(&sequences).unwrap();
+
+        assert_eq!(batch.len(), sequences.len());
+        for (sequence, embedding) in sequences.iter().zip(batch) {
+            for (a, b) in embedding.iter().zip(semantic.embed(sequence).unwrap().iter()) {
+                assert!((a - b).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_only_score_pairs_with_cross_encoder() {
+        let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        assert_eq!(semantic.model_kind(), ModelKind::BiEncoder);
+        assert!(matches!(semantic.score_pair("What is the capital of France?", "Paris is the capital of France."),
+            Err(SemanticError::WrongModelKind(ModelKind::BiEncoder))));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_reuse_environment_for_later_instances() {
+        let first = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let second = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+
+        assert_eq!(first.embed("hello").unwrap().0, second.embed("hello").unwrap().0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_swap_model_at_runtime() {
+        let mut semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let in_flight = semantic.clone();
+        let before = semantic.embed("hello").unwrap();
+
+        assert!(semantic.replace_model(b"not a model".to_vec()).is_err());
+        semantic.replace_model(std::fs::read("../model/model.onnx").unwrap()).unwrap();
+
+        assert_eq!(semantic.embed("hello").unwrap().0, before.0);
+        assert_eq!(in_flight.embed("hello").unwrap().0, before.0);
+    }
 }
 
 uniffi::include_scaffolding!("inference");
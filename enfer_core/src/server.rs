@@ -0,0 +1,151 @@
+//! A minimal HTTP embedding service, behind the `server` feature.
+//!
+//! - `POST /embed` with `{"text": "..."}` returns `{"embedding": [...]}`
+//! - `POST /embed_batch` with `{"texts": ["...", ...]}` returns `{"embeddings": [[...], ...]}`
+//!
+//! Failures return `{"error": "..."}`, with `400` for input that can't be embedded, `503` for transient failures
+//! worth retrying and `500` otherwise.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::embedding::{Semantic, SemanticError};
+
+#[derive(Debug, Deserialize)]
+struct EmbedRequest {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbedResponse {
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedBatchRequest {
+    texts: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbedBatchResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// The routes of the service, for mounting them into a larger application.
+pub fn router(semantic: Semantic) -> Router {
+    Router::new()
+        .route("/embed", post(embed))
+        .route("/embed_batch", post(embed_batch))
+        .with_state(semantic)
+}
+
+/// Serves the embedding endpoints on `addr` until the server fails. Must be called from within a Tokio runtime.
+///
+/// Example:
+/// ```rust,ignore
+/// use inference_core::Semantic;
+///
+/// #[tokio::main]
+/// async fn main() -> std::io::Result<()> {
+///     let semantic = Semantic::from_files("model.onnx", "tokenizer.json").unwrap();
+///     inference_core::server::serve(semantic, "0.0.0.0:8080").await
+/// }
+/// ```
+pub async fn serve(semantic: Semantic, addr: impl tokio::net::ToSocketAddrs) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+
+    axum::serve(listener, router(semantic)).await
+}
+
+async fn embed(State(semantic): State<Semantic>, Json(request): Json<EmbedRequest>) -> Result<Json<EmbedResponse>, ServerError> {
+    let embedding = semantic.embed_async(request.text).await?;
+
+    Ok(Json(EmbedResponse { embedding: embedding.into_vec() }))
+}
+
+async fn embed_batch(State(semantic): State<Semantic>, Json(request): Json<EmbedBatchRequest>) -> Result<Json<EmbedBatchResponse>, ServerError> {
+    let embeddings = tokio::task::spawn_blocking(move || {
+        let texts = request.texts.iter().map(String::as_str).collect::<Vec<_>>();
+        semantic.embed_batch(&texts)
+    }).await.map_err(|e| SemanticError::AsyncTaskFailed(e.to_string()))??;
+
+    Ok(Json(EmbedBatchResponse { embeddings: embeddings.into_iter().map(|embedding| embedding.into_vec()).collect() }))
+}
+
+struct ServerError(SemanticError);
+
+impl From<SemanticError> for ServerError {
+    fn from(error: SemanticError) -> Self {
+        ServerError(error)
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        (status_code(&self.0), Json(ErrorResponse { error: self.0.to_string() })).into_response()
+    }
+}
+
+fn status_code(error: &SemanticError) -> StatusCode {
+    match error {
+        SemanticError::EmptyInput
+        | SemanticError::EmptyTokenization
+        | SemanticError::MissingTemplateField(_)
+        | SemanticError::InputTooLong { .. }
+        | SemanticError::TokenizeEncodeError(_) => StatusCode::BAD_REQUEST,
+        error if error.is_retryable() => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn should_map_errors_to_status_codes() {
+        assert_eq!(status_code(&SemanticError::EmptyInput), StatusCode::BAD_REQUEST);
+        assert_eq!(status_code(&SemanticError::Timeout(std::time::Duration::from_secs(1))), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status_code(&SemanticError::InvalidPoolSize(0)), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ci", ignore)]
+    fn should_serve_embeddings() {
+        let semantic = Semantic::from_files("../model/model.onnx", "../model/tokenizer.json").unwrap();
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+
+        let response = runtime.block_on(router(semantic.clone()).oneshot(
+            Request::post("/embed")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"text": "Hello world!"}"#))
+                .unwrap()
+        )).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = runtime.block_on(axum::body::to_bytes(response.into_body(), usize::MAX)).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["embedding"].as_array().unwrap().len(), semantic.embedding_dim());
+
+        let response = runtime.block_on(router(semantic).oneshot(
+            Request::post("/embed_batch")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"texts": ["Hello world!", " "]}"#))
+                .unwrap()
+        )).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::embedding::SemanticError;
+
+/// The number of differing bits between two binary embeddings from [`crate::Embedding::to_binary`], which
+/// estimates how far apart the original vectors point. Fails when they don't have the same number of bytes.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{hamming_distance, Embedding};
+///
+/// let a = Embedding(vec![0.5, -0.1, 0.2]).to_binary();
+/// let b = Embedding(vec![0.4, 0.3, 0.2]).to_binary();
+/// assert_eq!(hamming_distance(&a, &b).unwrap(), 1);
+/// ```
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> Result<u32, SemanticError> {
+    if a.len() != b.len() {
+        return Err(SemanticError::DimensionMismatch { expected: a.len(), actual: b.len() });
+    }
+
+    Ok(a.iter().zip(b).map(|(a, b)| (a ^ b).count_ones()).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::Embedding;
+
+    #[test]
+    fn should_count_differing_signs() {
+        let embedding = Embedding((0..384).map(|i| if i % 3 == 0 { -1.0 - i as f32 } else { 0.5 + i as f32 }).collect());
+        let negated = Embedding(embedding.iter().map(|value| -value).collect());
+
+        let binary = embedding.to_binary();
+
+        assert_eq!(binary.len(), 48);
+        assert_eq!(hamming_distance(&binary, &binary).unwrap(), 0);
+        assert_eq!(hamming_distance(&binary, &negated.to_binary()).unwrap(), 384);
+    }
+
+    #[test]
+    fn should_reject_different_lengths() {
+        assert!(matches!(hamming_distance(&[0], &[0, 0]), Err(SemanticError::DimensionMismatch { expected: 1, actual: 2 })));
+    }
+}
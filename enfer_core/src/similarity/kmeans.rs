@@ -0,0 +1,164 @@
+use crate::embedding::{Embedding, SemanticError};
+
+/// How [`kmeans`] measures the distance of an embedding to a centroid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// `1 - cosine similarity`, which ignores the length of the vectors, the usual choice for text embeddings.
+    #[default]
+    Cosine,
+    /// The straight-line (L2) distance.
+    Euclidean,
+}
+
+impl DistanceMetric {
+    fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::Cosine => 1.0 - crate::embedding::math::cosine_similarity(a, b),
+            DistanceMetric::Euclidean => crate::embedding::math::euclidean_distance(a, b),
+        }
+    }
+}
+
+/// Groups the embeddings into `k` clusters with Lloyd's k-means, returning the cluster of every embedding in the
+/// order of `embeddings`. Clusters are numbered from `0` and the result is deterministic: the first centroid is the
+/// first embedding, every next one the embedding farthest from the centroids picked so far.
+///
+/// Stops once no assignment changes or after `max_iters` centroid updates, with `0` every embedding goes to its
+/// nearest initial centroid. No input gives no assignments, a `k` larger than
+/// the number of embeddings is lowered to it, and a `k` of `0` fails with [`SemanticError::InvalidClusterCount`].
+/// Fails with [`SemanticError::DimensionMismatch`] if the embeddings don't all have the same number of dimensions.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{kmeans, DistanceMetric, Embedding};
+///
+/// let embeddings = [Embedding(vec![1.0, 0.1]), Embedding(vec![0.0, 1.0]), Embedding(vec![0.9, 0.0])];
+/// let clusters = kmeans(&embeddings, 2, 10, DistanceMetric::Cosine).unwrap();
+/// assert_eq!(clusters, vec![0, 1, 0]);
+/// ```
+pub fn kmeans(embeddings: &[Embedding], k: usize, max_iters: usize, metric: DistanceMetric) -> Result<Vec<usize>, SemanticError> {
+    let Some(first) = embeddings.first() else { return Ok(vec![]) };
+    if k == 0 {
+        return Err(SemanticError::InvalidClusterCount(k));
+    }
+    if let Some(embedding) = embeddings.iter().find(|embedding| embedding.len() != first.len()) {
+        return Err(SemanticError::DimensionMismatch { expected: first.len(), actual: embedding.len() });
+    }
+
+    let mut centroids = initial_centroids(embeddings, k.min(embeddings.len()), metric);
+    let points = embeddings.iter().map(Embedding::as_slice).collect::<Vec<_>>();
+
+    Ok(lloyd(&points, &mut centroids, max_iters, metric))
+}
+
+/// Lloyd's algorithm from the given initial centroids, returning the index of the centroid closest to every point.
+/// Alternates moving every centroid to the mean of its points and reassigning the points, for at most `max_iters`
+/// updates, and stops early once no assignment changes. Clusters that end up empty keep their previous centroid.
+pub(crate) fn lloyd(points: &[&[f32]], centroids: &mut [Vec<f32>], max_iters: usize, metric: DistanceMetric) -> Vec<usize> {
+    let mut assignments = points.iter()
+        .map(|point| nearest(point, centroids, metric))
+        .collect::<Vec<_>>();
+
+    for _ in 0..max_iters {
+        let dim = centroids.first().map_or(0, Vec::len);
+        let mut sums = vec![vec![0.0; dim]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for (assignment, point) in assignments.iter().zip(points) {
+            counts[*assignment] += 1;
+            sums[*assignment].iter_mut().zip(point.iter()).for_each(|(sum, value)| *sum += value);
+        }
+        for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts).filter(|(_, count)| *count > 0) {
+            *centroid = sum.into_iter().map(|value| value / count as f32).collect();
+        }
+
+        let mut changed = false;
+        for (assignment, point) in assignments.iter_mut().zip(points) {
+            let closest = nearest(point, centroids, metric);
+            changed |= *assignment != closest;
+            *assignment = closest;
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    assignments
+}
+
+/// Farthest-point initialization: starts from the first embedding and keeps adding the one farthest from its
+/// nearest centroid.
+fn initial_centroids(embeddings: &[Embedding], k: usize, metric: DistanceMetric) -> Vec<Vec<f32>> {
+    let mut centroids = vec![embeddings[0].as_slice().to_vec()];
+    let mut distances = embeddings.iter()
+        .map(|embedding| metric.distance(embedding.as_slice(), &centroids[0]))
+        .collect::<Vec<_>>();
+
+    while centroids.len() < k {
+        let farthest = distances.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(0, |(index, _)| index);
+        let centroid = embeddings[farthest].as_slice().to_vec();
+        for (distance, embedding) in distances.iter_mut().zip(embeddings) {
+            *distance = distance.min(metric.distance(embedding.as_slice(), &centroid));
+        }
+        centroids.push(centroid);
+    }
+
+    centroids
+}
+
+/// The index of the centroid closest to the values.
+pub(crate) fn nearest(values: &[f32], centroids: &[Vec<f32>], metric: DistanceMetric) -> usize {
+    centroids.iter()
+        .map(|centroid| metric.distance(values, centroid))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two groups of vectors scattered around opposite corners, interleaved so the input order doesn't give them away.
+    fn two_clusters() -> Vec<Embedding> {
+        let mut state = 7u64;
+        let mut jitter = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / (1u64 << 31) as f32 - 0.5) * 0.2
+        };
+
+        (0..20)
+            .map(|i| {
+                let center = if i % 2 == 0 { [5.0, 5.0, 0.0] } else { [0.0, -5.0, 5.0] };
+                Embedding(center.iter().map(|value| value + jitter()).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_group_well_separated_clusters() {
+        let embeddings = two_clusters();
+
+        for metric in [DistanceMetric::Cosine, DistanceMetric::Euclidean] {
+            let clusters = kmeans(&embeddings, 2, 20, metric).unwrap();
+
+            assert_eq!(clusters.len(), embeddings.len());
+            for (index, cluster) in clusters.iter().enumerate() {
+                assert_eq!(*cluster, index % 2, "{:?} put embedding {} in the wrong cluster", metric, index);
+            }
+        }
+    }
+
+    #[test]
+    fn should_handle_degenerate_inputs() {
+        let embeddings = two_clusters();
+
+        assert_eq!(kmeans(&[], 3, 10, DistanceMetric::Cosine).unwrap(), Vec::<usize>::new());
+        assert_eq!(kmeans(&embeddings[..3], 5, 10, DistanceMetric::Euclidean).unwrap(), vec![0, 1, 2]);
+        assert!(matches!(kmeans(&embeddings, 0, 10, DistanceMetric::Cosine), Err(SemanticError::InvalidClusterCount(0))));
+        assert!(matches!(kmeans(&[Embedding(vec![1.0]), Embedding(vec![1.0, 0.0])], 1, 10, DistanceMetric::Cosine),
+            Err(SemanticError::DimensionMismatch { .. })));
+    }
+}
@@ -2,11 +2,18 @@ mod cosine_similarity;
 mod relevance_score;
 mod embedding_match;
 mod document_match;
+mod similarity_matrix;
+mod hamming_distance;
+mod kmeans;
 
 pub use cosine_similarity::CosineSimilarity;
 pub use relevance_score::RelevanceScore;
 pub use embedding_match::EmbeddingMatch;
 pub use document_match::DocumentMatch;
+pub use similarity_matrix::similarity_matrix;
+pub use hamming_distance::hamming_distance;
+pub use kmeans::{kmeans, DistanceMetric};
+pub(crate) use kmeans::{lloyd, nearest};
 
 use crate::embedding::Embedding;
 
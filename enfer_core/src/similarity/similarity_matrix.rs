@@ -0,0 +1,54 @@
+use crate::embedding::{Embedding, SemanticError};
+
+/// The pairwise cosine similarities of the embeddings, as an N×N matrix where `matrix[i][j]` is the similarity
+/// of `embeddings[i]` and `embeddings[j]`. The matrix is symmetric, with `1.0` on the diagonal for non-zero vectors.
+/// Fails if the embeddings don't all have the same number of dimensions.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{similarity_matrix, Embedding};
+///
+/// let matrix = similarity_matrix(&[Embedding(vec![1.0, 0.0]), Embedding(vec![0.0, 1.0])]).unwrap();
+/// assert_eq!(matrix[0][1], 0.0);
+/// ```
+pub fn similarity_matrix(embeddings: &[Embedding]) -> Result<Vec<Vec<f32>>, SemanticError> {
+    let mut matrix = vec![vec![0.0; embeddings.len()]; embeddings.len()];
+
+    for i in 0..embeddings.len() {
+        for j in i..embeddings.len() {
+            let similarity = embeddings[i].cosine_similarity(&embeddings[j])?;
+            matrix[i][j] = similarity;
+            matrix[j][i] = similarity;
+        }
+    }
+
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_be_symmetric_with_unit_diagonal() {
+        let embeddings = [Embedding(vec![1.0, 0.0, 0.0]), Embedding(vec![1.0, 1.0, 0.0]), Embedding(vec![0.0, 0.0, 2.0])];
+
+        let matrix = similarity_matrix(&embeddings).unwrap();
+
+        for (i, row) in matrix.iter().enumerate() {
+            assert!((row[i] - 1.0).abs() < 1e-6);
+            for (j, similarity) in row.iter().enumerate() {
+                assert_eq!(*similarity, matrix[j][i]);
+            }
+        }
+        assert!((matrix[0][1] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(matrix[0][2], 0.0);
+    }
+
+    #[test]
+    fn should_reject_mixed_dimensions() {
+        let embeddings = [Embedding(vec![1.0, 0.0]), Embedding(vec![1.0, 0.0, 0.0])];
+
+        assert!(matches!(similarity_matrix(&embeddings), Err(SemanticError::DimensionMismatch { .. })));
+    }
+}
@@ -1,5 +1,9 @@
 mod embedding_store;
 mod memory_store;
+mod vector_index;
+mod product_quantizer;
 
 pub use embedding_store::EmbeddingStore;
-pub use memory_store::InMemoryEmbeddingStore;
\ No newline at end of file
+pub use memory_store::InMemoryEmbeddingStore;
+pub use vector_index::VectorIndex;
+pub use product_quantizer::ProductQuantizer;
\ No newline at end of file
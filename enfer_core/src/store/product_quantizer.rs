@@ -0,0 +1,175 @@
+use crate::embedding::{Embedding, SemanticError};
+use crate::similarity::{lloyd, nearest, DistanceMetric};
+
+/// The number of k-means iterations run per subvector, training stops early once no assignment changes.
+const TRAIN_ITERATIONS: usize = 25;
+
+/// Compresses embeddings for disk-backed indexes by splitting every vector into `subvectors` equal slices and
+/// replacing each slice with the index of its nearest centroid, learned with k-means on a training set. A code takes
+/// one byte per subvector, e.g. 48 bytes instead of 1536 for a 384-dimensional embedding split 48 ways.
+///
+/// Decoded vectors are approximations, so cosine rankings over them are close to but not the same as the exact
+/// ones. More subvectors and bits improve the approximation at the cost of larger codes and slower training.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{Embedding, ProductQuantizer};
+///
+/// let embeddings = (0..32).map(|i| Embedding(vec![i as f32, 1.0, -(i as f32), 0.5])).collect::<Vec<_>>();
+/// let quantizer = ProductQuantizer::train(&embeddings, 2, 4).unwrap();
+///
+/// let codes = quantizer.encode(&embeddings[3]).unwrap();
+/// assert_eq!(codes.len(), 2);
+/// let decoded = quantizer.decode(&codes).unwrap();
+/// assert_eq!(decoded.len(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProductQuantizer {
+    dim: usize,
+    subvectors: usize,
+    /// The `2^bits` centroids of every subvector.
+    codebooks: Vec<Vec<Vec<f32>>>,
+}
+
+impl ProductQuantizer {
+    /// Learns `2^bits` centroids per subvector from the embeddings. `bits` is between 1 and 8, and the embeddings'
+    /// dimension must be divisible by `subvectors`. Training is deterministic, the initial centroids are spread
+    /// evenly over the training set.
+    pub fn train(embeddings: &[Embedding], subvectors: usize, bits: usize) -> Result<ProductQuantizer, SemanticError> {
+        let dim = embeddings.first()
+            .map(|embedding| embedding.len())
+            .ok_or_else(|| SemanticError::InvalidQuantizer("no training embeddings".to_string()))?;
+        if dim == 0 {
+            return Err(SemanticError::InvalidQuantizer("the training embeddings have no dimensions".to_string()));
+        }
+        if !(1..=8).contains(&bits) {
+            return Err(SemanticError::InvalidQuantizer(format!("{} bits don't fit a byte", bits)));
+        }
+        if subvectors == 0 || dim % subvectors != 0 {
+            return Err(SemanticError::InvalidQuantizer(format!("{} dimensions can't be split into {} subvectors", dim, subvectors)));
+        }
+        if let Some(embedding) = embeddings.iter().find(|embedding| embedding.len() != dim) {
+            return Err(SemanticError::DimensionMismatch { expected: dim, actual: embedding.len() });
+        }
+
+        let sub_dim = dim / subvectors;
+        let codebooks = (0..subvectors)
+            .map(|subvector| {
+                let slices = embeddings.iter()
+                    .map(|embedding| &embedding.as_slice()[subvector * sub_dim..(subvector + 1) * sub_dim])
+                    .collect::<Vec<_>>();
+                let centroids = 1 << bits;
+                let mut codebook = (0..centroids)
+                    .map(|centroid| slices[centroid * slices.len() / centroids].to_vec())
+                    .collect::<Vec<_>>();
+                lloyd(&slices, &mut codebook, TRAIN_ITERATIONS, DistanceMetric::Euclidean);
+
+                codebook
+            })
+            .collect();
+
+        Ok(ProductQuantizer { dim, subvectors, codebooks })
+    }
+
+    /// The number of dimensions of the embeddings it encodes.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Replaces every subvector with the index of its nearest centroid, one byte per subvector.
+    pub fn encode(&self, embedding: &Embedding) -> Result<Vec<u8>, SemanticError> {
+        if embedding.len() != self.dim {
+            return Err(SemanticError::DimensionMismatch { expected: self.dim, actual: embedding.len() });
+        }
+
+        Ok(embedding.as_slice()
+            .chunks(self.sub_dim())
+            .zip(&self.codebooks)
+            .map(|(slice, codebook)| nearest(slice, codebook, DistanceMetric::Euclidean) as u8)
+            .collect())
+    }
+
+    /// Rebuilds an approximate embedding from the codes written by [`ProductQuantizer::encode`].
+    pub fn decode(&self, codes: &[u8]) -> Result<Embedding, SemanticError> {
+        if codes.len() != self.subvectors {
+            return Err(SemanticError::DimensionMismatch { expected: self.subvectors, actual: codes.len() });
+        }
+
+        let mut values = Vec::with_capacity(self.dim);
+        for (code, codebook) in codes.iter().zip(&self.codebooks) {
+            let centroid = codebook.get(*code as usize)
+                .ok_or_else(|| SemanticError::InvalidQuantizer(format!("no centroid {}", code)))?;
+            values.extend_from_slice(centroid);
+        }
+
+        Ok(Embedding(values))
+    }
+
+    fn sub_dim(&self) -> usize {
+        self.dim / self.subvectors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vectors scattered around a few directions, generated with a fixed-seed LCG so the test is reproducible.
+    fn synthetic(count: usize, dim: usize) -> Vec<Embedding> {
+        let mut state = 42u64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as f32 / (1u64 << 31) as f32 - 0.5
+        };
+        let centers = (0..8).map(|_| (0..dim).map(|_| next()).collect::<Vec<_>>()).collect::<Vec<_>>();
+
+        (0..count)
+            .map(|i| Embedding(centers[i % centers.len()].iter().map(|x| x + 0.3 * next()).collect()))
+            .collect()
+    }
+
+    fn top_k(query: &Embedding, candidates: &[Embedding], k: usize) -> Vec<usize> {
+        let mut scored = candidates.iter()
+            .enumerate()
+            .map(|(index, candidate)| (index, query.cosine_similarity(candidate).unwrap()))
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        scored.into_iter().take(k).map(|(index, _)| index).collect()
+    }
+
+    #[test]
+    fn should_preserve_nearest_neighbours() {
+        let embeddings = synthetic(400, 32);
+        let quantizer = ProductQuantizer::train(&embeddings, 16, 6).unwrap();
+        let decoded = embeddings.iter()
+            .map(|embedding| quantizer.decode(&quantizer.encode(embedding).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        let queries = synthetic(420, 32).split_off(400);
+        let found = queries.iter()
+            .map(|query| {
+                let exact = top_k(query, &embeddings, 10);
+                top_k(query, &decoded, 10).iter().filter(|index| exact.contains(index)).count()
+            })
+            .sum::<usize>();
+
+        let recall = found as f32 / (queries.len() * 10) as f32;
+        assert!(recall >= 0.7, "recall@10 was {}", recall);
+    }
+
+    #[test]
+    fn should_reject_invalid_parameters() {
+        let embeddings = synthetic(16, 6);
+
+        assert!(matches!(ProductQuantizer::train(&embeddings, 4, 4), Err(SemanticError::InvalidQuantizer(_))));
+        assert!(matches!(ProductQuantizer::train(&embeddings, 3, 9), Err(SemanticError::InvalidQuantizer(_))));
+        assert!(matches!(ProductQuantizer::train(&[], 3, 4), Err(SemanticError::InvalidQuantizer(_))));
+        assert!(matches!(ProductQuantizer::train(&[Embedding(vec![]), Embedding(vec![])], 1, 4), Err(SemanticError::InvalidQuantizer(_))));
+
+        let quantizer = ProductQuantizer::train(&embeddings, 3, 2).unwrap();
+        assert!(matches!(quantizer.encode(&Embedding(vec![0.0; 4])), Err(SemanticError::DimensionMismatch { .. })));
+        assert!(matches!(quantizer.decode(&[0, 1]), Err(SemanticError::DimensionMismatch { expected: 3, actual: 2 })));
+        assert!(matches!(quantizer.decode(&[0, 1, 4]), Err(SemanticError::InvalidQuantizer(_))));
+    }
+}
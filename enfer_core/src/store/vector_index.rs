@@ -0,0 +1,92 @@
+use crate::embedding::Embedding;
+
+/// A brute-force nearest neighbour index, scoring every entry by cosine similarity.
+///
+/// Example:
+/// ```rust
+/// use inference_core::{Embedding, VectorIndex};
+///
+/// let mut index = VectorIndex::new();
+/// index.add(1, Embedding(vec![1.0, 0.0]));
+/// index.add(2, Embedding(vec![0.0, 1.0]));
+///
+/// let results = index.search(&Embedding(vec![0.9, 0.1]), 1);
+/// assert_eq!(results[0].0, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VectorIndex<Id: Clone> {
+    entries: Vec<(Id, Embedding)>,
+}
+
+impl<Id: Clone> VectorIndex<Id> {
+    pub fn new() -> Self {
+        VectorIndex { entries: vec![] }
+    }
+
+    pub fn add(&mut self, id: Id, embedding: Embedding) {
+        self.entries.push((id, embedding));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns up to `k` entries most similar to the query, sorted by descending cosine similarity.
+    /// Entries whose dimension differs from the query can't be compared and are skipped.
+    pub fn search(&self, query: &Embedding, k: usize) -> Vec<(Id, f32)> {
+        let mut scored: Vec<(Id, f32)> = self.entries
+            .iter()
+            .filter_map(|(id, embedding)| {
+                embedding.cosine_similarity(query).ok().map(|score| (id.clone(), score))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(k);
+        scored
+    }
+}
+
+impl<Id: Clone> Default for VectorIndex<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_return_top_k_sorted_descending() {
+        let mut index = VectorIndex::new();
+        index.add("x", Embedding(vec![1.0, 0.0]));
+        index.add("y", Embedding(vec![0.0, 1.0]));
+        index.add("xy", Embedding(vec![1.0, 1.0]));
+
+        let results = index.search(&Embedding(vec![1.0, 0.2]), 2);
+
+        assert_eq!(results.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec!["x", "xy"]);
+        assert!(results[0].1 > results[1].1);
+    }
+
+    #[test]
+    fn should_return_everything_when_k_exceeds_len() {
+        let mut index = VectorIndex::new();
+        index.add(1, Embedding(vec![1.0, 0.0]));
+        index.add(2, Embedding(vec![0.0, 1.0]));
+
+        assert_eq!(index.search(&Embedding(vec![1.0, 0.0]), 10).len(), 2);
+    }
+
+    #[test]
+    fn should_return_nothing_for_empty_index() {
+        let index: VectorIndex<u32> = VectorIndex::new();
+
+        assert!(index.search(&Embedding(vec![1.0, 0.0]), 3).is_empty());
+    }
+}
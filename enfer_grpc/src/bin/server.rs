@@ -107,7 +107,7 @@ impl Tokenizer for MyTokenizer {
 
         let reply = tokenizer::EncodeReply {
             text: text.into(),
-            embedding: embedding.0,
+            embedding: embedding.into_vec(),
         };
 
         Ok(Response::new(reply))